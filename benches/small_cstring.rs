@@ -0,0 +1,30 @@
+#![feature(test)]
+
+extern crate cstr_core;
+extern crate test;
+
+use cstr_core::{CString, SmallCString};
+use test::{black_box, Bencher};
+
+const SHORT: &str = "user.name";
+const LONG: &str = "org.freedesktop.DBus.Properties.PropertiesChanged";
+
+#[bench]
+fn c_string_new_short(b: &mut Bencher) {
+    b.iter(|| CString::new(black_box(SHORT)).unwrap());
+}
+
+#[bench]
+fn small_c_string_new_short(b: &mut Bencher) {
+    b.iter(|| SmallCString::new(black_box(SHORT)).unwrap());
+}
+
+#[bench]
+fn c_string_new_long(b: &mut Bencher) {
+    b.iter(|| CString::new(black_box(LONG)).unwrap());
+}
+
+#[bench]
+fn small_c_string_new_long(b: &mut Bencher) {
+    b.iter(|| SmallCString::new(black_box(LONG)).unwrap());
+}
@@ -0,0 +1,473 @@
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use alloc::borrow::Borrow;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{self, AtomicUsize};
+use core::{fmt, mem, ops, slice};
+
+use {c_char, AllocError, CStr, CString};
+
+/// A soft limit on the reference counts, well below `usize::MAX` so that
+/// overflowing it is detected before the counts wrap around.
+const MAX_REFCOUNT: usize = isize::MAX as usize;
+
+/// Header placed right before the bytes of an [`ArcCString`].
+///
+/// [`ArcCString`]: struct.ArcCString.html
+struct Header {
+    strong: AtomicUsize,
+    /// Number of weak references, plus one shared by all strong references.
+    weak: AtomicUsize,
+    /// Length of the string, including its nul terminator.
+    len: usize,
+}
+
+fn layout(len: usize) -> Option<Layout> {
+    let (layout, offset) = Layout::new::<Header>()
+        .extend(Layout::array::<u8>(len).ok()?)
+        .ok()?;
+    debug_assert_eq!(offset, mem::size_of::<Header>());
+    Some(layout.pad_to_align())
+}
+
+/// Returns a pointer to the bytes following a header.
+#[inline]
+fn data(header: NonNull<Header>) -> *mut u8 {
+    unsafe { (header.as_ptr() as *mut u8).add(mem::size_of::<Header>()) }
+}
+
+/// A thread-safe reference-counted C string, one pointer wide.
+///
+/// Unlike `Arc<CStr>`, which is a fat pointer to the string, an `ArcCString`
+/// is a single pointer to a header holding the reference counts and the
+/// length, followed by the string and its nul terminator. Cloning it only
+/// increments a reference count, and the pointer returned by [`as_ptr`] stays
+/// valid for as long as any clone exists.
+///
+/// [`as_ptr`]: struct.CStr.html#method.as_ptr
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{ArcCString, CString};
+///
+/// let name = ArcCString::from(CString::new("eth0").unwrap());
+/// let clone = name.clone();
+/// assert_eq!(name.as_ptr(), clone.as_ptr());
+/// assert_eq!(ArcCString::strong_count(&name), 2);
+///
+/// let weak = ArcCString::downgrade(&name);
+/// drop((name, clone));
+/// assert!(weak.upgrade().is_none());
+/// ```
+pub struct ArcCString {
+    ptr: NonNull<Header>,
+}
+
+unsafe impl Send for ArcCString {}
+unsafe impl Sync for ArcCString {}
+
+impl ArcCString {
+    /// Copies `bytes`, which must be a valid C string with its nul
+    /// terminator, into a new allocation.
+    ///
+    /// Returns the layout which could not be allocated on failure.
+    fn try_from_bytes_with_nul(bytes: &[u8]) -> Result<ArcCString, Option<Layout>> {
+        let layout = layout(bytes.len()).ok_or(None)?;
+        unsafe {
+            let ptr = NonNull::new(alloc(layout) as *mut Header).ok_or(Some(layout))?;
+            ptr.as_ptr().write(Header {
+                strong: AtomicUsize::new(1),
+                weak: AtomicUsize::new(1),
+                len: bytes.len(),
+            });
+            ptr::copy_nonoverlapping(bytes.as_ptr(), data(ptr), bytes.len());
+            Ok(ArcCString { ptr })
+        }
+    }
+
+    fn from_bytes_with_nul(bytes: &[u8]) -> ArcCString {
+        match ArcCString::try_from_bytes_with_nul(bytes) {
+            Ok(s) => s,
+            Err(Some(layout)) => handle_alloc_error(layout),
+            Err(None) => panic!("capacity overflow"),
+        }
+    }
+
+    /// Copies a string into a new `ArcCString`, without aborting on
+    /// allocation failure.
+    ///
+    /// The standard library offers no fallible way to create an `Arc<CStr>`
+    /// or `Rc<CStr>`, so this is the way to create a shared string when
+    /// running out of memory must be recoverable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AllocError`] if memory could not be allocated.
+    ///
+    /// [`AllocError`]: struct.AllocError.html
+    #[inline]
+    pub fn try_from_c_str(s: &CStr) -> Result<ArcCString, AllocError> {
+        ArcCString::try_from_bytes_with_nul(s.to_bytes_with_nul()).map_err(|_| AllocError::new())
+    }
+
+    #[inline]
+    fn header(&self) -> &Header {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Extracts a [`CStr`] slice containing the entire string.
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe {
+            let bytes = slice::from_raw_parts(data(self.ptr), self.header().len);
+            CStr::from_bytes_with_nul_unchecked(bytes)
+        }
+    }
+
+    /// Returns the number of `ArcCString`s pointing to this string.
+    #[inline]
+    pub fn strong_count(this: &ArcCString) -> usize {
+        this.header().strong.load(atomic::Ordering::Acquire)
+    }
+
+    /// Returns the number of [`WeakCString`]s pointing to this string.
+    ///
+    /// [`WeakCString`]: struct.WeakCString.html
+    #[inline]
+    pub fn weak_count(this: &ArcCString) -> usize {
+        this.header().weak.load(atomic::Ordering::Acquire) - 1
+    }
+
+    /// Creates a new [`WeakCString`] pointing to this string.
+    ///
+    /// [`WeakCString`]: struct.WeakCString.html
+    pub fn downgrade(this: &ArcCString) -> WeakCString {
+        let old = this.header().weak.fetch_add(1, atomic::Ordering::Relaxed);
+        if old > MAX_REFCOUNT {
+            this.header().weak.fetch_sub(1, atomic::Ordering::Relaxed);
+            panic!("reference count overflow");
+        }
+        WeakCString { ptr: this.ptr }
+    }
+
+    /// Returns whether two `ArcCString`s point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(this: &ArcCString, other: &ArcCString) -> bool {
+        this.ptr == other.ptr
+    }
+
+    /// Consumes the `ArcCString` and returns a pointer to the string, without
+    /// decrementing the reference count.
+    ///
+    /// The pointer can be turned back into an `ArcCString` with
+    /// [`from_raw`](#method.from_raw), to release it.
+    #[inline]
+    pub fn into_raw(this: ArcCString) -> *const c_char {
+        // Derive the pointer from the header, rather than from the `CStr`,
+        // so that `from_raw` may use it to reach the header again.
+        let ptr = data(this.ptr) as *const c_char;
+        mem::forget(this);
+        ptr
+    }
+
+    /// Retakes ownership of a string returned by
+    /// [`into_raw`](#method.into_raw).
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `ArcCString::into_raw`, and each call
+    /// to `into_raw` must be matched by at most one call to `from_raw`.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const c_char) -> ArcCString {
+        let header = (ptr as *mut u8).sub(mem::size_of::<Header>()) as *mut Header;
+        ArcCString {
+            ptr: NonNull::new_unchecked(header),
+        }
+    }
+}
+
+impl Clone for ArcCString {
+    #[inline]
+    fn clone(&self) -> ArcCString {
+        let old = self.header().strong.fetch_add(1, atomic::Ordering::Relaxed);
+        if old > MAX_REFCOUNT {
+            self.header().strong.fetch_sub(1, atomic::Ordering::Relaxed);
+            panic!("reference count overflow");
+        }
+        ArcCString { ptr: self.ptr }
+    }
+}
+
+impl Drop for ArcCString {
+    #[inline]
+    fn drop(&mut self) {
+        if self.header().strong.fetch_sub(1, atomic::Ordering::Release) != 1 {
+            return;
+        }
+        atomic::fence(atomic::Ordering::Acquire);
+        // Release the weak reference held by the strong references together.
+        drop(WeakCString { ptr: self.ptr });
+    }
+}
+
+impl ops::Deref for ArcCString {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl AsRef<CStr> for ArcCString {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl Borrow<CStr> for ArcCString {
+    #[inline]
+    fn borrow(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl Default for ArcCString {
+    #[inline]
+    fn default() -> ArcCString {
+        ArcCString::from_bytes_with_nul(b"\0")
+    }
+}
+
+impl fmt::Debug for ArcCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl<'a> From<&'a CStr> for ArcCString {
+    #[inline]
+    fn from(s: &'a CStr) -> ArcCString {
+        ArcCString::from_bytes_with_nul(s.to_bytes_with_nul())
+    }
+}
+
+impl From<CString> for ArcCString {
+    #[inline]
+    fn from(s: CString) -> ArcCString {
+        ArcCString::from_bytes_with_nul(s.as_bytes_with_nul())
+    }
+}
+
+impl PartialEq for ArcCString {
+    #[inline]
+    fn eq(&self, other: &ArcCString) -> bool {
+        ArcCString::ptr_eq(self, other) || self.as_c_str() == other.as_c_str()
+    }
+}
+
+impl Eq for ArcCString {}
+
+impl PartialOrd for ArcCString {
+    #[inline]
+    fn partial_cmp(&self, other: &ArcCString) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArcCString {
+    #[inline]
+    fn cmp(&self, other: &ArcCString) -> Ordering {
+        self.as_c_str().cmp(other.as_c_str())
+    }
+}
+
+impl Hash for ArcCString {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_c_str().hash(state)
+    }
+}
+
+/// A weak reference to an [`ArcCString`].
+///
+/// A `WeakCString` does not keep the string alive: it must be
+/// [`upgrade`]d to access it, which fails once every `ArcCString` has been
+/// dropped.
+///
+/// [`ArcCString`]: struct.ArcCString.html
+/// [`upgrade`]: #method.upgrade
+pub struct WeakCString {
+    ptr: NonNull<Header>,
+}
+
+unsafe impl Send for WeakCString {}
+unsafe impl Sync for WeakCString {}
+
+impl WeakCString {
+    #[inline]
+    fn header(&self) -> &Header {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns an [`ArcCString`] pointing to the string, or `None` if it has
+    /// already been dropped.
+    ///
+    /// [`ArcCString`]: struct.ArcCString.html
+    pub fn upgrade(&self) -> Option<ArcCString> {
+        let strong = &self.header().strong;
+        let mut n = strong.load(atomic::Ordering::Relaxed);
+        loop {
+            if n == 0 {
+                return None;
+            }
+            if n > MAX_REFCOUNT {
+                panic!("reference count overflow");
+            }
+            match strong.compare_exchange_weak(
+                n,
+                n + 1,
+                atomic::Ordering::Acquire,
+                atomic::Ordering::Relaxed,
+            ) {
+                Ok(_) => return Some(ArcCString { ptr: self.ptr }),
+                Err(old) => n = old,
+            }
+        }
+    }
+
+    /// Returns the number of [`ArcCString`]s pointing to the string.
+    ///
+    /// [`ArcCString`]: struct.ArcCString.html
+    #[inline]
+    pub fn strong_count(&self) -> usize {
+        self.header().strong.load(atomic::Ordering::Acquire)
+    }
+
+    /// Returns the number of `WeakCString`s pointing to the string, or 0 if
+    /// the string has already been dropped.
+    #[inline]
+    pub fn weak_count(&self) -> usize {
+        let weak = self.header().weak.load(atomic::Ordering::Acquire);
+        if self.strong_count() == 0 {
+            0
+        } else {
+            weak - 1
+        }
+    }
+}
+
+impl Clone for WeakCString {
+    #[inline]
+    fn clone(&self) -> WeakCString {
+        let old = self.header().weak.fetch_add(1, atomic::Ordering::Relaxed);
+        if old > MAX_REFCOUNT {
+            self.header().weak.fetch_sub(1, atomic::Ordering::Relaxed);
+            panic!("reference count overflow");
+        }
+        WeakCString { ptr: self.ptr }
+    }
+}
+
+impl Drop for WeakCString {
+    #[inline]
+    fn drop(&mut self) {
+        if self.header().weak.fetch_sub(1, atomic::Ordering::Release) != 1 {
+            return;
+        }
+        atomic::fence(atomic::Ordering::Acquire);
+        unsafe {
+            let layout = layout(self.header().len).unwrap();
+            dealloc(self.ptr.as_ptr() as *mut u8, layout);
+        }
+    }
+}
+
+impl fmt::Debug for WeakCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("(Weak)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::vec::Vec;
+
+    #[test]
+    fn thin() {
+        assert_eq!(mem::size_of::<ArcCString>(), mem::size_of::<usize>());
+        assert_eq!(
+            mem::size_of::<Option<ArcCString>>(),
+            mem::size_of::<usize>()
+        );
+        assert_eq!(mem::size_of::<WeakCString>(), mem::size_of::<usize>());
+    }
+
+    #[test]
+    fn clone_and_weak() {
+        let a = ArcCString::from(CString::new("foo").unwrap());
+        assert_eq!(a.to_bytes_with_nul(), b"foo\0");
+        let b = a.clone();
+        assert!(ArcCString::ptr_eq(&a, &b));
+        assert_eq!(ArcCString::strong_count(&a), 2);
+
+        let w = ArcCString::downgrade(&a);
+        let w2 = w.clone();
+        assert_eq!((ArcCString::weak_count(&a), w.weak_count()), (2, 2));
+        drop(a);
+        assert_eq!(w.upgrade(), Some(b.clone()));
+        drop(b);
+        assert_eq!(w.strong_count(), 0);
+        assert_eq!(w.weak_count(), 0);
+        assert!(w2.upgrade().is_none());
+    }
+
+    #[test]
+    fn raw() {
+        let a = ArcCString::from(CStr::from_bytes_with_nul(b"raw\0").unwrap());
+        let ptr = ArcCString::into_raw(a.clone());
+        assert_eq!(ArcCString::strong_count(&a), 2);
+        let b = unsafe { ArcCString::from_raw(ptr) };
+        assert_eq!(b.as_ptr(), a.as_ptr());
+        drop(b);
+        assert_eq!(ArcCString::strong_count(&a), 1);
+        assert_eq!(ArcCString::default().to_bytes(), b"");
+        assert_eq!(ArcCString::try_from_c_str(&a), Ok(a));
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let ptr = ArcCString::into_raw(ArcCString::from(
+            CStr::from_bytes_with_nul(b"trip\0").unwrap(),
+        ));
+        let a = unsafe { ArcCString::from_raw(ptr) };
+        let b = a.clone();
+        assert_eq!(ArcCString::strong_count(&a), 2);
+        assert_eq!(b.to_bytes(), b"trip");
+        drop(a);
+        assert_eq!(ArcCString::strong_count(&b), 1);
+        drop(b);
+    }
+
+    #[test]
+    fn threads() {
+        let a = ArcCString::from(CString::new("shared").unwrap());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let a = a.clone();
+                thread::spawn(move || a.to_bytes().len())
+            })
+            .collect();
+        for h in handles {
+            assert_eq!(h.join().unwrap(), 6);
+        }
+        assert_eq!(ArcCString::strong_count(&a), 1);
+    }
+}
@@ -0,0 +1,180 @@
+use core::borrow::Borrow;
+use core::mem::MaybeUninit;
+use core::{fmt, ops, slice};
+
+use fill::{strnlen, FillError};
+use {c_char, CStr};
+
+/// A C string stored inline in a fixed-size buffer of `N` bytes.
+///
+/// This is an owned C string which does not need an allocator, so it can be
+/// used to retrieve strings from foreign functions in `no_std` environments.
+/// The buffer holds the nul terminator, so at most `N - 1` bytes of string
+/// data fit in it.
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{c_char, ArrayCString};
+///
+/// let s = unsafe {
+///     ArrayCString::<16>::from_ffi_fill(|buf: *mut c_char, _len| {
+///         std::ptr::copy_nonoverlapping(b"host\0".as_ptr() as *const c_char, buf, 5);
+///         Ok::<(), ()>(())
+///     })
+/// }
+/// .unwrap();
+/// assert_eq!(s.to_bytes(), b"host");
+/// ```
+#[derive(Clone, Copy)]
+pub struct ArrayCString<const N: usize> {
+    // Invariant: `buf[..=len]` is initialized, contains no nul byte before
+    // `len`, and `buf[len]` is zero.
+    buf: [MaybeUninit<u8>; N],
+    len: usize,
+}
+
+impl<const N: usize> ArrayCString<N> {
+    /// Creates an empty C string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero, since there is no room for the nul terminator.
+    #[inline]
+    pub const fn new() -> ArrayCString<N> {
+        assert!(N > 0, "ArrayCString needs room for a nul terminator");
+        let mut buf = [MaybeUninit::uninit(); N];
+        buf[0] = MaybeUninit::new(0);
+        ArrayCString { buf, len: 0 }
+    }
+
+    /// Creates a C string by letting a foreign function write into the
+    /// buffer.
+    ///
+    /// This is the inline counterpart of [`CString::from_ffi_fill`]: `f` is
+    /// called with a pointer to the uninitialized buffer and its length `N`,
+    /// and the string is everything up to the first nul byte it wrote.
+    ///
+    /// [`CString::from_ffi_fill`]: struct.CString.html#method.from_ffi_fill
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FillError::Failed`] if `f` returns an error, and
+    /// [`FillError::NotNulTerminated`] if the buffer holds no nul byte.
+    ///
+    /// [`FillError::Failed`]: enum.FillError.html#variant.Failed
+    /// [`FillError::NotNulTerminated`]: enum.FillError.html#variant.NotNulTerminated
+    ///
+    /// # Safety
+    ///
+    /// If `f` returns `Ok`, it must have initialized the buffer up to and
+    /// including a nul byte, or in its entirety.
+    pub unsafe fn from_ffi_fill<F, E>(f: F) -> Result<ArrayCString<N>, FillError<E>>
+    where
+        F: FnOnce(*mut c_char, usize) -> Result<(), E>,
+    {
+        let mut buf = [MaybeUninit::uninit(); N];
+        f(buf.as_mut_ptr() as *mut c_char, N).map_err(FillError::Failed)?;
+        match strnlen(buf.as_ptr() as *const u8, N) {
+            Some(len) => Ok(ArrayCString { buf, len }),
+            None => Err(FillError::NotNulTerminated),
+        }
+    }
+
+    /// Returns the size of the buffer, including room for the nul terminator.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Extracts a [`CStr`] slice containing the entire string.
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe {
+            let bytes = slice::from_raw_parts(self.buf.as_ptr() as *const u8, self.len + 1);
+            CStr::from_bytes_with_nul_unchecked(bytes)
+        }
+    }
+}
+
+impl<const N: usize> Default for ArrayCString<N> {
+    #[inline]
+    fn default() -> ArrayCString<N> {
+        ArrayCString::new()
+    }
+}
+
+impl<const N: usize> ops::Deref for ArrayCString<N> {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl<const N: usize> AsRef<CStr> for ArrayCString<N> {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+impl<const N: usize> Borrow<CStr> for ArrayCString<N> {
+    #[inline]
+    fn borrow(&self) -> &CStr {
+        self
+    }
+}
+
+impl<const N: usize> fmt::Debug for ArrayCString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl<const N: usize> PartialEq for ArrayCString<N> {
+    #[inline]
+    fn eq(&self, other: &ArrayCString<N>) -> bool {
+        self.as_c_str() == other.as_c_str()
+    }
+}
+
+impl<const N: usize> Eq for ArrayCString<N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ptr;
+
+    #[test]
+    fn fill() {
+        let s = unsafe {
+            ArrayCString::<8>::from_ffi_fill(|buf, len| {
+                assert_eq!(len, 8);
+                ptr::copy_nonoverlapping(b"abc\0".as_ptr() as *const c_char, buf, 4);
+                Ok::<(), ()>(())
+            })
+        };
+        let s = s.unwrap();
+        assert_eq!(s.to_bytes_with_nul(), b"abc\0");
+        assert_eq!(s.capacity(), 8);
+
+        let s = unsafe {
+            ArrayCString::<3>::from_ffi_fill(|buf, _| {
+                ptr::copy_nonoverlapping(b"abc".as_ptr() as *const c_char, buf, 3);
+                Ok::<(), ()>(())
+            })
+        };
+        assert_eq!(s, Err(FillError::NotNulTerminated));
+    }
+
+    #[test]
+    fn empty() {
+        let s = ArrayCString::<1>::new();
+        assert_eq!(s.to_bytes_with_nul(), b"\0");
+        assert_eq!(s, ArrayCString::default());
+    }
+}
@@ -0,0 +1,599 @@
+#[cfg(feature = "alloc")]
+use alloc::borrow::ToOwned;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::error::Error;
+use core::hash::{Hash, Hasher};
+use core::{fmt, ops, str};
+
+#[cfg(feature = "alloc")]
+use CString;
+use {c_char, CStr, CUtf8Str};
+
+/// Returns the position of the first byte outside of printable ASCII.
+const fn find_non_printable(bytes: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x20..=0x7e => i += 1,
+            _ => return Some(i),
+        }
+    }
+    None
+}
+
+/// An error indicating that a string is not printable ASCII.
+///
+/// This is returned when creating an [`AsciiCStr`] or an `AsciiCString`.
+///
+/// [`AsciiCStr`]: struct.AsciiCStr.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AsciiError {
+    position: usize,
+    byte: Option<u8>,
+}
+
+impl AsciiError {
+    /// Returns the position of the offending byte, or the length of the
+    /// string if it was missing its nul terminator.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the offending byte, or `None` if the string was missing its
+    /// nul terminator.
+    #[inline]
+    pub fn byte(&self) -> Option<u8> {
+        self.byte
+    }
+}
+
+impl fmt::Display for AsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.byte {
+            Some(b) => write!(
+                f,
+                "non-printable byte {:#04x} found at position: {}",
+                b, self.position
+            ),
+            None => write!(f, "data provided is not nul terminated"),
+        }
+    }
+}
+
+impl Error for AsciiError {}
+
+/// A borrowed C string made of printable ASCII characters only.
+///
+/// Every byte before the nul terminator is in the range `0x20..=0x7e`, which
+/// excludes control characters such as tabs and newlines. Such a string is
+/// both a valid `str` and a valid [`CStr`], and converts to either for free.
+///
+/// Strings can be checked at runtime with [`from_bytes_with_nul`] and
+/// [`from_c_str`], or when compiling with the [`ascii_c_str!`] macro.
+///
+/// [`CStr`]: struct.CStr.html
+/// [`from_bytes_with_nul`]: #method.from_bytes_with_nul
+/// [`from_c_str`]: #method.from_c_str
+/// [`ascii_c_str!`]: macro.ascii_c_str.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::AsciiCStr;
+///
+/// let s = AsciiCStr::from_bytes_with_nul(b"model-name\0").unwrap();
+/// assert_eq!(s.as_str(), "model-name");
+/// assert_eq!(s.as_c_str().to_bytes(), b"model-name");
+///
+/// let err = AsciiCStr::from_bytes_with_nul(b"tab\there\0").unwrap_err();
+/// assert_eq!(err.position(), 3);
+/// assert_eq!(err.byte(), Some(b'\t'));
+/// ```
+#[repr(transparent)]
+pub struct AsciiCStr {
+    /// The string, with its nul terminator.
+    inner: [u8],
+}
+
+impl AsciiCStr {
+    /// Creates a string from bytes ending with a nul terminator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AsciiError`] for the first byte which is not printable
+    /// ASCII, including any interior nul byte, or if the bytes do not end
+    /// with a nul byte.
+    ///
+    /// [`AsciiError`]: struct.AsciiError.html
+    pub const fn from_bytes_with_nul(bytes: &[u8]) -> Result<&AsciiCStr, AsciiError> {
+        let len = bytes.len();
+        if len == 0 || bytes[len - 1] != 0 {
+            return Err(AsciiError {
+                position: len,
+                byte: None,
+            });
+        }
+        let (s, _) = bytes.split_at(len - 1);
+        match find_non_printable(s) {
+            Some(i) => Err(AsciiError {
+                position: i,
+                byte: Some(bytes[i]),
+            }),
+            None => Ok(unsafe { AsciiCStr::from_bytes_with_nul_unchecked(bytes) }),
+        }
+    }
+
+    /// Creates a string from a C string after checking that it is printable
+    /// ASCII.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AsciiError`] for the first byte which is not printable
+    /// ASCII.
+    ///
+    /// [`AsciiError`]: struct.AsciiError.html
+    pub fn from_c_str(s: &CStr) -> Result<&AsciiCStr, AsciiError> {
+        AsciiCStr::from_bytes_with_nul(s.to_bytes_with_nul())
+    }
+
+    /// Creates a string from bytes without checking them.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must end with a nul byte, and all other bytes must be in the
+    /// range `0x20..=0x7e`.
+    #[inline]
+    pub const unsafe fn from_bytes_with_nul_unchecked(bytes: &[u8]) -> &AsciiCStr {
+        &*(bytes as *const [u8] as *const AsciiCStr)
+    }
+
+    /// Returns the string, without its nul terminator.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.inner[..self.inner.len() - 1]) }
+    }
+
+    /// Returns the string as a [`CStr`].
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.inner) }
+    }
+
+    /// Returns the string as a [`CUtf8Str`].
+    ///
+    /// [`CUtf8Str`]: struct.CUtf8Str.html
+    #[inline]
+    pub fn as_c_utf8_str(&self) -> &CUtf8Str {
+        unsafe { CUtf8Str::from_bytes_with_nul_unchecked(&self.inner) }
+    }
+
+    /// Returns the inner pointer to this C string.
+    ///
+    /// See [`CStr::as_ptr`](struct.CStr.html#method.as_ptr).
+    #[inline]
+    pub fn as_ptr(&self) -> *const c_char {
+        self.inner.as_ptr() as *const c_char
+    }
+}
+
+/// Creates an [`AsciiCStr`] from a string literal, checking it when
+/// compiling.
+///
+/// The nul terminator is appended to the literal. Compilation fails if the
+/// literal contains a byte which is not printable ASCII.
+///
+/// [`AsciiCStr`]: struct.AsciiCStr.html
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate cstr_core;
+///
+/// use cstr_core::AsciiCStr;
+///
+/// const NAME: &AsciiCStr = ascii_c_str!("compatible");
+///
+/// # fn main() {
+/// assert_eq!(NAME.as_c_str().to_bytes_with_nul(), b"compatible\0");
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate cstr_core;
+///
+/// # fn main() {
+/// let s = ascii_c_str!("caf\u{e9}");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! ascii_c_str {
+    ($s:expr) => {{
+        const S: &$crate::AsciiCStr =
+            match $crate::AsciiCStr::from_bytes_with_nul(concat!($s, "\0").as_bytes()) {
+                Ok(s) => s,
+                Err(_) => panic!("string literal is not printable ASCII"),
+            };
+        S
+    }};
+}
+
+impl ops::Deref for AsciiCStr {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for AsciiCStr {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<CStr> for AsciiCStr {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl AsRef<CUtf8Str> for AsciiCStr {
+    #[inline]
+    fn as_ref(&self) -> &CUtf8Str {
+        self.as_c_utf8_str()
+    }
+}
+
+impl<'a> TryFrom<&'a CStr> for &'a AsciiCStr {
+    type Error = AsciiError;
+
+    #[inline]
+    fn try_from(s: &'a CStr) -> Result<&'a AsciiCStr, AsciiError> {
+        AsciiCStr::from_c_str(s)
+    }
+}
+
+impl<'a> From<&'a AsciiCStr> for &'a CStr {
+    #[inline]
+    fn from(s: &'a AsciiCStr) -> &'a CStr {
+        s.as_c_str()
+    }
+}
+
+impl<'a> From<&'a AsciiCStr> for &'a str {
+    #[inline]
+    fn from(s: &'a AsciiCStr) -> &'a str {
+        s.as_str()
+    }
+}
+
+impl<'a> Default for &'a AsciiCStr {
+    #[inline]
+    fn default() -> &'a AsciiCStr {
+        unsafe { AsciiCStr::from_bytes_with_nul_unchecked(b"\0") }
+    }
+}
+
+impl fmt::Debug for AsciiCStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for AsciiCStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for AsciiCStr {
+    #[inline]
+    fn eq(&self, other: &AsciiCStr) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for AsciiCStr {}
+
+impl PartialEq<str> for AsciiCStr {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialOrd for AsciiCStr {
+    #[inline]
+    fn partial_cmp(&self, other: &AsciiCStr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AsciiCStr {
+    #[inline]
+    fn cmp(&self, other: &AsciiCStr) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+/// Hashes like the `str` without the nul terminator.
+impl Hash for AsciiCStr {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ToOwned for AsciiCStr {
+    type Owned = AsciiCString;
+
+    #[inline]
+    fn to_owned(&self) -> AsciiCString {
+        AsciiCString {
+            inner: self.as_c_str().to_owned(),
+        }
+    }
+}
+
+/// An owned C string made of printable ASCII characters only.
+///
+/// This is the owned counterpart of [`AsciiCStr`]. It wraps a [`CString`],
+/// which it converts into without copying.
+///
+/// [`AsciiCStr`]: struct.AsciiCStr.html
+/// [`CString`]: struct.CString.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::AsciiCString;
+///
+/// let s = AsciiCString::new("Acme Corp").unwrap();
+/// assert_eq!(s.as_str(), "Acme Corp");
+///
+/// let err = AsciiCString::new("Acme\nCorp").unwrap_err();
+/// assert_eq!((err.position(), err.byte()), (4, Some(b'\n')));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AsciiCString {
+    inner: CString,
+}
+
+#[cfg(feature = "alloc")]
+impl AsciiCString {
+    /// Creates a string from a container of bytes, appending the nul
+    /// terminator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AsciiError`] for the first byte which is not printable
+    /// ASCII, including any nul byte.
+    ///
+    /// [`AsciiError`]: struct.AsciiError.html
+    pub fn new<T: Into<Vec<u8>>>(t: T) -> Result<AsciiCString, AsciiError> {
+        let v = t.into();
+        if let Some(i) = find_non_printable(&v) {
+            return Err(AsciiError {
+                position: i,
+                byte: Some(v[i]),
+            });
+        }
+        Ok(AsciiCString {
+            inner: unsafe { CString::from_vec_unchecked(v) },
+        })
+    }
+
+    /// Creates a string from a `CString` after checking that it is
+    /// printable ASCII, reusing its buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AsciiError`] for the first byte which is not printable
+    /// ASCII.
+    ///
+    /// [`AsciiError`]: struct.AsciiError.html
+    pub fn from_c_string(s: CString) -> Result<AsciiCString, AsciiError> {
+        AsciiCStr::from_c_str(&s)?;
+        Ok(AsciiCString { inner: s })
+    }
+
+    /// Returns the string as an [`AsciiCStr`].
+    ///
+    /// [`AsciiCStr`]: struct.AsciiCStr.html
+    #[inline]
+    pub fn as_ascii_c_str(&self) -> &AsciiCStr {
+        unsafe { AsciiCStr::from_bytes_with_nul_unchecked(self.inner.as_bytes_with_nul()) }
+    }
+
+    /// Converts the string into a `CString`, without copying.
+    #[inline]
+    pub fn into_c_string(self) -> CString {
+        self.inner
+    }
+
+    /// Converts the string into a `String`, without its nul terminator and
+    /// without copying.
+    #[inline]
+    pub fn into_string(self) -> String {
+        unsafe { String::from_utf8_unchecked(self.inner.into_bytes()) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ops::Deref for AsciiCString {
+    type Target = AsciiCStr;
+
+    #[inline]
+    fn deref(&self) -> &AsciiCStr {
+        self.as_ascii_c_str()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<str> for AsciiCString {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<CStr> for AsciiCString {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<AsciiCStr> for AsciiCString {
+    #[inline]
+    fn as_ref(&self) -> &AsciiCStr {
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Borrow<AsciiCStr> for AsciiCString {
+    #[inline]
+    fn borrow(&self) -> &AsciiCStr {
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for AsciiCString {
+    #[inline]
+    fn default() -> AsciiCString {
+        AsciiCString {
+            inner: CString::default(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Hash for AsciiCString {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Debug for AsciiCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for AsciiCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> From<&'a AsciiCStr> for AsciiCString {
+    #[inline]
+    fn from(s: &'a AsciiCStr) -> AsciiCString {
+        s.to_owned()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TryFrom<CString> for AsciiCString {
+    type Error = AsciiError;
+
+    #[inline]
+    fn try_from(s: CString) -> Result<AsciiCString, AsciiError> {
+        AsciiCString::from_c_string(s)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<AsciiCString> for CString {
+    #[inline]
+    fn from(s: AsciiCString) -> CString {
+        s.into_c_string()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<AsciiCString> for String {
+    #[inline]
+    fn from(s: AsciiCString) -> String {
+        s.into_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CStrError;
+
+    #[test]
+    fn borrowed() {
+        const S: &AsciiCStr = ascii_c_str!("a b~");
+        assert_eq!(S.as_str(), "a b~");
+        assert_eq!(S.as_c_str().to_bytes_with_nul(), b"a b~\0");
+        assert_eq!(S.as_c_utf8_str().as_str(), "a b~");
+        assert_eq!(S.as_ptr(), S.as_c_str().as_ptr());
+        assert_eq!(*S, *"a b~");
+        assert_eq!(format!("{} {:?}", S, S), "a b~ \"a b~\"");
+        assert_eq!(<&AsciiCStr>::default().as_str(), "");
+
+        let err = AsciiCStr::from_bytes_with_nul(b"ab\x7f\0").unwrap_err();
+        assert_eq!((err.position(), err.byte()), (2, Some(0x7f)));
+        let err = AsciiCStr::from_bytes_with_nul(b"a\0b\0").unwrap_err();
+        assert_eq!((err.position(), err.byte()), (1, Some(0)));
+        let err = AsciiCStr::from_bytes_with_nul(b"ab").unwrap_err();
+        assert_eq!((err.position(), err.byte()), (2, None));
+        assert!(AsciiCStr::from_bytes_with_nul(b"").is_err());
+        assert_eq!(CStrError::from(err), CStrError::Ascii(err));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn owned() {
+        use std::string::ToString;
+
+        let s = AsciiCString::new("id-1").unwrap();
+        assert_eq!(s.as_str(), "id-1");
+        assert_eq!(s, AsciiCString::from(&*s));
+        assert_eq!(s.clone().into_string(), "id-1");
+        assert_eq!(AsciiCString::default().as_str(), "");
+
+        let c = s.into_c_string();
+        let ptr = c.as_ptr();
+        let s = AsciiCString::from_c_string(c).unwrap();
+        assert_eq!(s.as_ptr(), ptr);
+
+        let err = AsciiCString::new(&b"\xc3\xa9"[..]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "non-printable byte 0xc3 found at position: 0"
+        );
+        assert_eq!(
+            AsciiCString::new("a\0").unwrap_err(),
+            AsciiCStr::from_bytes_with_nul(b"a\0\0").unwrap_err()
+        );
+    }
+}
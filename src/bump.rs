@@ -0,0 +1,143 @@
+use core::fmt::{self, Write};
+
+use bumpalo::collections::String;
+use bumpalo::Bump;
+use memchr::memchr;
+
+use {CStr, NulError};
+
+impl CStr {
+    /// Copies this string into a bump arena.
+    ///
+    /// The copy is freed along with everything else in the arena, when it is
+    /// reset or dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bumpalo;
+    /// # extern crate cstr_core;
+    ///
+    /// use bumpalo::Bump;
+    /// use cstr_core::CStr;
+    ///
+    /// let bump = Bump::new();
+    /// let s = CStr::from_bytes_with_nul(b"foo\0").unwrap();
+    /// let copy = s.to_owned_in(&bump);
+    /// assert_eq!(copy, s);
+    /// assert_ne!(copy.as_ptr(), s.as_ptr());
+    /// ```
+    #[inline]
+    pub fn to_owned_in<'b>(&self, bump: &'b Bump) -> &'b CStr {
+        let bytes = bump.alloc_slice_copy(self.to_bytes_with_nul());
+        unsafe { CStr::from_bytes_with_nul_unchecked(bytes) }
+    }
+}
+
+/// Allocates a C string holding the contents of `s` in a bump arena.
+///
+/// # Errors
+///
+/// Returns a [`NulError`] if `s` contains a nul byte. Nothing is allocated in
+/// the arena in that case.
+///
+/// [`NulError`]: struct.NulError.html
+///
+/// # Examples
+///
+/// ```
+/// extern crate bumpalo;
+/// # extern crate cstr_core;
+///
+/// use bumpalo::Bump;
+/// use cstr_core::alloc_cstr_from_str;
+///
+/// let bump = Bump::new();
+/// let s = alloc_cstr_from_str(&bump, "key").unwrap();
+/// assert_eq!(s.to_bytes_with_nul(), b"key\0");
+/// assert!(alloc_cstr_from_str(&bump, "k\0ey").is_err());
+/// ```
+pub fn alloc_cstr_from_str<'b>(bump: &'b Bump, s: &str) -> Result<&'b CStr, NulError> {
+    let bytes = s.as_bytes();
+    if let Some(i) = memchr(0, bytes) {
+        return Err(NulError(i, bytes.to_vec()));
+    }
+    let buf = bump.alloc_slice_fill_copy(bytes.len() + 1, 0u8);
+    buf[..bytes.len()].copy_from_slice(bytes);
+    Ok(unsafe { CStr::from_bytes_with_nul_unchecked(buf) })
+}
+
+/// Implementation of `c_format_in!`.
+///
+/// On error the formatted string is dropped, which only returns its space to
+/// the arena if it is still the last allocation there.
+#[doc(hidden)]
+pub fn format_in<'b>(bump: &'b Bump, args: fmt::Arguments) -> Result<&'b CStr, NulError> {
+    let mut s = String::new_in(bump);
+    s.write_fmt(args)
+        .expect("a formatting trait implementation returned an error");
+    if let Some(i) = memchr(0, s.as_bytes()) {
+        return Err(NulError(i, s.as_bytes().to_vec()));
+    }
+    s.push('\0');
+    Ok(unsafe { CStr::from_bytes_with_nul_unchecked(s.into_bump_str().as_bytes()) })
+}
+
+/// Formats a C string into a bump arena.
+///
+/// This takes a `&Bump` followed by the same arguments as `format!`, and
+/// evaluates to a `Result<&CStr, NulError>`, which is an error if the
+/// formatted string contains a nul byte.
+///
+/// The string is formatted into the arena before it is checked, so a failed
+/// call may still use up arena space until the arena is reset. The space is
+/// only given back if nothing else was allocated in the arena meanwhile.
+///
+/// # Examples
+///
+/// ```
+/// extern crate bumpalo;
+/// #[macro_use]
+/// extern crate cstr_core;
+///
+/// use bumpalo::Bump;
+///
+/// # fn main() {
+/// let bump = Bump::new();
+/// let s = c_format_in!(&bump, "{}-{}", "eth", 0).unwrap();
+/// assert_eq!(s.to_bytes(), b"eth-0");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! c_format_in {
+    ($bump:expr, $($arg:tt)*) => {
+        $crate::__format_in($bump, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alloc() {
+        let bump = Bump::new();
+        let s = alloc_cstr_from_str(&bump, "abc").unwrap();
+        assert_eq!(s.to_bytes_with_nul(), b"abc\0");
+        assert_eq!(s.to_owned_in(&bump), s);
+        assert_eq!(alloc_cstr_from_str(&bump, "").unwrap().to_bytes(), b"");
+
+        let err = alloc_cstr_from_str(&bump, "a\0").unwrap_err();
+        assert_eq!(err.nul_position(), 1);
+    }
+
+    #[test]
+    fn format() {
+        let bump = Bump::new();
+        let s = c_format_in!(&bump, "{:03}", 7).unwrap();
+        assert_eq!(s.to_bytes_with_nul(), b"007\0");
+
+        let err = c_format_in!(&bump, "a{}", '\0').unwrap_err();
+        assert_eq!(err.into_vec(), b"a\0");
+    }
+}
@@ -0,0 +1,501 @@
+#[cfg(feature = "alloc")]
+use alloc::borrow::ToOwned;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
+use core::str::{self, Utf8Error};
+use core::{fmt, ops};
+
+#[cfg(feature = "alloc")]
+use memchr::memchr;
+
+use {c_char, CStr, FromBytesWithNulError};
+#[cfg(feature = "alloc")]
+use {CString, IntoStringError, NulError};
+
+/// A borrowed C string which is known to be valid UTF-8.
+///
+/// `CStr::to_str` checks the string each time it is called. A `CUtf8Str` is
+/// checked once, when it is created, and then derefs to `str` (without the
+/// nul terminator) for free, while [`as_c_str`] still gives a [`CStr`] to
+/// pass to C.
+///
+/// [`as_c_str`]: #method.as_c_str
+/// [`CStr`]: struct.CStr.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{CStr, CUtf8Str};
+///
+/// let c = CStr::from_bytes_with_nul(b"h\xc3\xa9llo\0").unwrap();
+/// let s = CUtf8Str::from_c_str(c).unwrap();
+/// assert_eq!(s.len(), 6);
+/// assert!(s.starts_with("hé"));
+/// assert_eq!(s.as_c_str(), c);
+/// ```
+#[repr(transparent)]
+pub struct CUtf8Str {
+    /// The string, with its nul terminator.
+    inner: str,
+}
+
+impl CUtf8Str {
+    /// Wraps a C string after checking that it is valid UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `Utf8Error` from checking the string, without its nul
+    /// terminator.
+    pub fn from_c_str(s: &CStr) -> Result<&CUtf8Str, Utf8Error> {
+        str::from_utf8(s.to_bytes())?;
+        Ok(unsafe { CUtf8Str::from_bytes_with_nul_unchecked(s.to_bytes_with_nul()) })
+    }
+
+    /// Wraps a string slice ending with its only nul byte.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FromBytesWithNulError`] if the string is not nul
+    /// terminated or contains an interior nul byte.
+    ///
+    /// [`FromBytesWithNulError`]: struct.FromBytesWithNulError.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::CUtf8Str;
+    ///
+    /// let s = CUtf8Str::from_str_with_nul("foo\0").unwrap();
+    /// assert_eq!(&**s, "foo");
+    /// assert!(CUtf8Str::from_str_with_nul("foo").is_err());
+    /// ```
+    pub fn from_str_with_nul(s: &str) -> Result<&CUtf8Str, FromBytesWithNulError> {
+        CStr::from_bytes_with_nul(s.as_bytes())?;
+        Ok(unsafe { CUtf8Str::from_bytes_with_nul_unchecked(s.as_bytes()) })
+    }
+
+    /// Wraps bytes without checking that they are valid UTF-8 ending with
+    /// their only nul byte.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be valid UTF-8, end with a nul byte and contain no other
+    /// nul byte.
+    #[inline]
+    pub unsafe fn from_bytes_with_nul_unchecked(bytes: &[u8]) -> &CUtf8Str {
+        &*(bytes as *const [u8] as *const CUtf8Str)
+    }
+
+    /// Returns the string, without its nul terminator.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.inner[..self.inner.len() - 1]
+    }
+
+    /// Returns the string, including its nul terminator.
+    #[inline]
+    pub fn as_str_with_nul(&self) -> &str {
+        &self.inner
+    }
+
+    /// Returns the string as a [`CStr`].
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe { CStr::from_bytes_with_nul_unchecked(self.inner.as_bytes()) }
+    }
+
+    /// Returns the inner pointer to this C string.
+    ///
+    /// See [`CStr::as_ptr`](struct.CStr.html#method.as_ptr).
+    #[inline]
+    pub fn as_ptr(&self) -> *const c_char {
+        self.inner.as_ptr() as *const c_char
+    }
+}
+
+impl ops::Deref for CUtf8Str {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for CUtf8Str {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<CStr> for CUtf8Str {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl AsRef<CUtf8Str> for CUtf8Str {
+    #[inline]
+    fn as_ref(&self) -> &CUtf8Str {
+        self
+    }
+}
+
+impl<'a> TryFrom<&'a CStr> for &'a CUtf8Str {
+    type Error = Utf8Error;
+
+    #[inline]
+    fn try_from(s: &'a CStr) -> Result<&'a CUtf8Str, Utf8Error> {
+        CUtf8Str::from_c_str(s)
+    }
+}
+
+impl<'a> From<&'a CUtf8Str> for &'a CStr {
+    #[inline]
+    fn from(s: &'a CUtf8Str) -> &'a CStr {
+        s.as_c_str()
+    }
+}
+
+impl<'a> Default for &'a CUtf8Str {
+    #[inline]
+    fn default() -> &'a CUtf8Str {
+        unsafe { CUtf8Str::from_bytes_with_nul_unchecked(b"\0") }
+    }
+}
+
+impl fmt::Debug for CUtf8Str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for CUtf8Str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl PartialEq for CUtf8Str {
+    #[inline]
+    fn eq(&self, other: &CUtf8Str) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl Eq for CUtf8Str {}
+
+impl PartialEq<str> for CUtf8Str {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialOrd for CUtf8Str {
+    #[inline]
+    fn partial_cmp(&self, other: &CUtf8Str) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CUtf8Str {
+    #[inline]
+    fn cmp(&self, other: &CUtf8Str) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+/// Hashes like the `str` without the nul terminator.
+impl Hash for CUtf8Str {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ToOwned for CUtf8Str {
+    type Owned = CUtf8String;
+
+    #[inline]
+    fn to_owned(&self) -> CUtf8String {
+        CUtf8String {
+            inner: self.inner.into(),
+        }
+    }
+}
+
+/// An owned C string which is known to be valid UTF-8.
+///
+/// This is the owned counterpart of [`CUtf8Str`]. Like a [`CString`], it
+/// holds an exactly-sized buffer ending with a nul byte, so it converts into
+/// a `String` or a `CString` without copying the string.
+///
+/// [`CUtf8Str`]: struct.CUtf8Str.html
+/// [`CString`]: struct.CString.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::CUtf8String;
+///
+/// let mut s = String::with_capacity(8);
+/// s.push_str("name");
+/// let c = CUtf8String::new(s).unwrap();
+/// assert_eq!(c.as_c_str().to_bytes_with_nul(), b"name\0");
+/// assert_eq!(c.into_string(), "name");
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CUtf8String {
+    /// The string, with its nul terminator.
+    inner: Box<str>,
+}
+
+#[cfg(feature = "alloc")]
+impl CUtf8String {
+    /// Creates a C string from a `String`, appending the nul terminator.
+    ///
+    /// As with [`CString::new`], the buffer of the `String` is reused, but it
+    /// may be reallocated to make room for the terminator or to shrink it to
+    /// fit.
+    ///
+    /// [`CString::new`]: struct.CString.html#method.new
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NulError`] if the string contains a nul byte.
+    ///
+    /// [`NulError`]: struct.NulError.html
+    pub fn new<T: Into<String>>(s: T) -> Result<CUtf8String, NulError> {
+        let mut inner = s.into();
+        if let Some(i) = memchr(0, inner.as_bytes()) {
+            return Err(NulError(i, inner.into_bytes()));
+        }
+        inner.reserve_exact(1);
+        inner.push('\0');
+        Ok(CUtf8String {
+            inner: inner.into_boxed_str(),
+        })
+    }
+
+    /// Creates a C string from a `CString` after checking that it is valid
+    /// UTF-8, reusing its buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IntoStringError`] holding the original `CString` if it
+    /// is not valid UTF-8.
+    ///
+    /// [`IntoStringError`]: struct.IntoStringError.html
+    pub fn from_c_string(s: CString) -> Result<CUtf8String, IntoStringError> {
+        if let Err(error) = str::from_utf8(s.as_bytes()) {
+            return Err(IntoStringError { inner: s, error });
+        }
+        let bytes = s.into_bytes_with_nul();
+        Ok(CUtf8String {
+            inner: unsafe { String::from_utf8_unchecked(bytes) }.into_boxed_str(),
+        })
+    }
+
+    /// Returns the string as a [`CUtf8Str`].
+    ///
+    /// [`CUtf8Str`]: struct.CUtf8Str.html
+    #[inline]
+    pub fn as_c_utf8_str(&self) -> &CUtf8Str {
+        unsafe { CUtf8Str::from_bytes_with_nul_unchecked(self.inner.as_bytes()) }
+    }
+
+    /// Converts the string into a `String`, without its nul terminator.
+    #[inline]
+    pub fn into_string(self) -> String {
+        let mut inner = self.inner.into_string();
+        inner.pop();
+        inner
+    }
+
+    /// Converts the string into a `CString`, without copying.
+    #[inline]
+    pub fn into_c_string(self) -> CString {
+        CString {
+            inner: self.inner.into_boxed_bytes(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ops::Deref for CUtf8String {
+    type Target = CUtf8Str;
+
+    #[inline]
+    fn deref(&self) -> &CUtf8Str {
+        self.as_c_utf8_str()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<str> for CUtf8String {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<CStr> for CUtf8String {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<CUtf8Str> for CUtf8String {
+    #[inline]
+    fn as_ref(&self) -> &CUtf8Str {
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Borrow<CUtf8Str> for CUtf8String {
+    #[inline]
+    fn borrow(&self) -> &CUtf8Str {
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for CUtf8String {
+    #[inline]
+    fn default() -> CUtf8String {
+        <&CUtf8Str>::default().to_owned()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Hash for CUtf8String {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Debug for CUtf8String {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for CUtf8String {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> From<&'a CUtf8Str> for CUtf8String {
+    #[inline]
+    fn from(s: &'a CUtf8Str) -> CUtf8String {
+        s.to_owned()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TryFrom<String> for CUtf8String {
+    type Error = NulError;
+
+    #[inline]
+    fn try_from(s: String) -> Result<CUtf8String, NulError> {
+        CUtf8String::new(s)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TryFrom<CString> for CUtf8String {
+    type Error = IntoStringError;
+
+    #[inline]
+    fn try_from(s: CString) -> Result<CUtf8String, IntoStringError> {
+        CUtf8String::from_c_string(s)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<CUtf8String> for String {
+    #[inline]
+    fn from(s: CUtf8String) -> String {
+        s.into_string()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<CUtf8String> for CString {
+    #[inline]
+    fn from(s: CUtf8String) -> CString {
+        s.into_c_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrowed() {
+        let s = CUtf8Str::from_str_with_nul("h\u{e9}\0").unwrap();
+        assert_eq!(s.as_str(), "h\u{e9}");
+        assert_eq!(s.as_str_with_nul(), "h\u{e9}\0");
+        assert_eq!(s.as_c_str().to_bytes(), "h\u{e9}".as_bytes());
+        assert_eq!(s.as_ptr(), s.as_c_str().as_ptr());
+        assert_eq!(*s, *"h\u{e9}");
+        assert_eq!(format!("{} {:?}", s, s), "h\u{e9} \"h\u{e9}\"");
+        assert_eq!(<&CUtf8Str>::default().as_str(), "");
+
+        assert!(CUtf8Str::from_str_with_nul("a\0b\0").is_err());
+        let c = CStr::from_bytes_with_nul(b"\xff\0").unwrap();
+        assert!(CUtf8Str::from_c_str(c).is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn owned() {
+        let mut s = String::with_capacity(4);
+        s.push_str("abc");
+        let ptr = s.as_ptr();
+        let c = CUtf8String::new(s).unwrap();
+        assert_eq!(c.as_ptr() as *const u8, ptr);
+        assert_eq!(c.as_str(), "abc");
+        assert_eq!(c.clone().into_string(), "abc");
+        assert_eq!(c.clone().into_c_string(), CString::new("abc").unwrap());
+        assert_eq!(c, CUtf8String::from(&*c));
+        assert_eq!(CUtf8String::new("a\0c").unwrap_err().nul_position(), 1);
+        assert_eq!(CUtf8String::default().as_str_with_nul(), "\0");
+
+        let cs = CString::new("xyz").unwrap();
+        let ptr = cs.as_ptr();
+        let c = CUtf8String::from_c_string(cs).unwrap();
+        assert_eq!(c.as_ptr(), ptr);
+        let cs = c.into_c_string();
+        assert_eq!(cs.as_ptr(), ptr);
+        let s = CUtf8String::from_c_string(cs).unwrap().into_string();
+        assert_eq!(s.as_ptr(), ptr as *const u8);
+        assert_eq!(s, "xyz");
+        let err = CUtf8String::from_c_string(CString::new(&b"\xff"[..]).unwrap());
+        assert_eq!(err.unwrap_err().into_cstring().as_bytes(), b"\xff");
+    }
+}
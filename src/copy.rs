@@ -0,0 +1,253 @@
+use core::error::Error;
+use core::{fmt, ptr};
+
+use {c_char, CStr};
+
+/// The outcome of [`CStr::copy_to_buffer`].
+///
+/// Like the return value of `strlcpy`, this reports the length of the source
+/// string so that truncation can be detected.
+///
+/// [`CStr::copy_to_buffer`]: struct.CStr.html#method.copy_to_buffer
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CopyResult {
+    copied: usize,
+    source_len: usize,
+}
+
+impl CopyResult {
+    /// Returns the number of bytes copied, not counting the nul terminator.
+    #[inline]
+    pub fn copied(&self) -> usize {
+        self.copied
+    }
+
+    /// Returns the length of the source string, not counting its nul
+    /// terminator.
+    #[inline]
+    pub fn source_len(&self) -> usize {
+        self.source_len
+    }
+
+    /// Returns whether the string was truncated to fit in the buffer.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.copied < self.source_len
+    }
+}
+
+/// An error returned from [`CStr::try_copy_to_buffer`] when the string and
+/// its nul terminator do not fit in the buffer.
+///
+/// [`CStr::try_copy_to_buffer`]: struct.CStr.html#method.try_copy_to_buffer
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BufferTooSmallError {
+    required: usize,
+    available: usize,
+}
+
+impl BufferTooSmallError {
+    pub(crate) fn new(required: usize, available: usize) -> BufferTooSmallError {
+        BufferTooSmallError {
+            required,
+            available,
+        }
+    }
+
+    /// Returns the buffer size needed to hold the string, including its nul
+    /// terminator.
+    #[inline]
+    pub fn required(&self) -> usize {
+        self.required
+    }
+
+    /// Returns the size of the buffer that was provided.
+    #[inline]
+    pub fn available(&self) -> usize {
+        self.available
+    }
+}
+
+impl fmt::Display for BufferTooSmallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "buffer of {} bytes is too small for a string needing {} bytes",
+            self.available, self.required
+        )
+    }
+}
+
+impl Error for BufferTooSmallError {}
+
+/// Copies `src` and a nul terminator to the start of `dst`, which must be
+/// large enough.
+#[inline]
+fn copy_with_nul(src: &[u8], dst: &mut [c_char]) {
+    debug_assert!(src.len() < dst.len());
+    unsafe {
+        ptr::copy_nonoverlapping(src.as_ptr() as *const c_char, dst.as_mut_ptr(), src.len());
+    }
+    dst[src.len()] = 0;
+}
+
+impl CStr {
+    /// Copies this string into a fixed-size buffer, truncating it if needed.
+    ///
+    /// This behaves like `strlcpy`: at most `dst.len() - 1` bytes are copied,
+    /// and the copy is always followed by a nul terminator, unless `dst` is
+    /// empty in which case nothing is written. Bytes of `dst` past the
+    /// terminator are left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{c_char, CStr};
+    ///
+    /// let mut ifr_name = [0 as c_char; 4];
+    /// let name = CStr::from_bytes_with_nul(b"wlan0\0").unwrap();
+    /// let res = name.copy_to_buffer(&mut ifr_name);
+    /// assert!(res.is_truncated());
+    /// assert_eq!(res.copied(), 3);
+    /// assert_eq!(res.source_len(), 5);
+    /// assert_eq!(ifr_name, [b'w' as c_char, b'l' as c_char, b'a' as c_char, 0]);
+    /// ```
+    pub fn copy_to_buffer(&self, dst: &mut [c_char]) -> CopyResult {
+        let src = self.to_bytes();
+        let copied = src.len().min(dst.len().saturating_sub(1));
+        if !dst.is_empty() {
+            copy_with_nul(&src[..copied], dst);
+        }
+        CopyResult {
+            copied,
+            source_len: src.len(),
+        }
+    }
+
+    /// Copies this string into a fixed-size buffer, truncating it on a UTF-8
+    /// character boundary if needed.
+    ///
+    /// This is the same as [`copy_to_buffer`], except that when the string is
+    /// truncated, a multi-byte UTF-8 sequence is never split: the copy stops
+    /// before the first byte of the sequence that does not fit.
+    ///
+    /// [`copy_to_buffer`]: #method.copy_to_buffer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{c_char, CStr};
+    ///
+    /// let mut buf = [0 as c_char; 4];
+    /// let s = CStr::from_bytes_with_nul("ab\u{e9}\0".as_bytes()).unwrap();
+    /// let res = s.copy_to_buffer_utf8(&mut buf);
+    /// assert_eq!(res.copied(), 2);
+    /// assert_eq!(buf[..3], [b'a' as c_char, b'b' as c_char, 0]);
+    /// ```
+    pub fn copy_to_buffer_utf8(&self, dst: &mut [c_char]) -> CopyResult {
+        let src = self.to_bytes();
+        let mut copied = src.len().min(dst.len().saturating_sub(1));
+        // Back off while the first byte that does not fit is a continuation
+        // byte of the character being cut.
+        while copied > 0 && copied < src.len() && src[copied] & 0xc0 == 0x80 {
+            copied -= 1;
+        }
+        if !dst.is_empty() {
+            copy_with_nul(&src[..copied], dst);
+        }
+        CopyResult {
+            copied,
+            source_len: src.len(),
+        }
+    }
+
+    /// Copies this string into a fixed-size buffer, failing instead of
+    /// truncating it.
+    ///
+    /// On success, returns the number of bytes copied, not counting the nul
+    /// terminator.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BufferTooSmallError`] if `dst` cannot hold the string and
+    /// its nul terminator. `dst` is left untouched in that case.
+    ///
+    /// [`BufferTooSmallError`]: struct.BufferTooSmallError.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{c_char, CStr};
+    ///
+    /// let mut sun_path = [0 as c_char; 108];
+    /// let path = CStr::from_bytes_with_nul(b"/run/app.sock\0").unwrap();
+    /// assert_eq!(path.try_copy_to_buffer(&mut sun_path), Ok(13));
+    ///
+    /// let err = path.try_copy_to_buffer(&mut sun_path[..8]).unwrap_err();
+    /// assert_eq!(err.required(), 14);
+    /// ```
+    pub fn try_copy_to_buffer(&self, dst: &mut [c_char]) -> Result<usize, BufferTooSmallError> {
+        let src = self.to_bytes();
+        if src.len() >= dst.len() {
+            return Err(BufferTooSmallError::new(src.len() + 1, dst.len()));
+        }
+        copy_with_nul(src, dst);
+        Ok(src.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    fn bytes(buf: &[c_char]) -> &[u8] {
+        unsafe { &*(buf as *const [c_char] as *const [u8]) }
+    }
+
+    #[test]
+    fn copy() {
+        let mut buf = [1 as c_char; 8];
+        let res = c(b"abc\0").copy_to_buffer(&mut buf);
+        assert_eq!((res.copied(), res.source_len()), (3, 3));
+        assert!(!res.is_truncated());
+        assert_eq!(bytes(&buf), b"abc\0\x01\x01\x01\x01");
+
+        let res = c(b"abcdefghij\0").copy_to_buffer(&mut buf);
+        assert_eq!((res.copied(), res.source_len()), (7, 10));
+        assert!(res.is_truncated());
+        assert_eq!(bytes(&buf), b"abcdefg\0");
+
+        let res = c(b"abc\0").copy_to_buffer(&mut []);
+        assert_eq!((res.copied(), res.source_len()), (0, 3));
+        assert!(res.is_truncated());
+    }
+
+    #[test]
+    fn copy_utf8() {
+        // "a€" is `a` followed by three bytes.
+        let s = c("a\u{20ac}b\0".as_bytes());
+        for &(len, copied) in &[(1, 0), (2, 1), (4, 1), (5, 4), (6, 5)] {
+            let mut buf = [1 as c_char; 6];
+            let res = s.copy_to_buffer_utf8(&mut buf[..len]);
+            assert_eq!(res.copied(), copied, "buffer of {}", len);
+            assert_eq!(&bytes(&buf)[..copied], &s.to_bytes()[..copied]);
+            assert_eq!(buf[copied], 0);
+        }
+    }
+
+    #[test]
+    fn try_copy() {
+        let mut buf = [1 as c_char; 4];
+        assert_eq!(c(b"abc\0").try_copy_to_buffer(&mut buf), Ok(3));
+        assert_eq!(bytes(&buf), b"abc\0");
+
+        let mut buf = [1 as c_char; 4];
+        let err = c(b"abcd\0").try_copy_to_buffer(&mut buf).unwrap_err();
+        assert_eq!((err.required(), err.available()), (5, 4));
+        assert_eq!(bytes(&buf), b"\x01\x01\x01\x01");
+    }
+}
@@ -0,0 +1,140 @@
+//! Conversions between the types of this crate and `core::ffi::CStr` /
+//! `alloc::ffi::CString`.
+//!
+//! Both pairs of types share the same representation, so every conversion
+//! here is a cast or reuses the existing allocation.
+
+use core::ffi;
+
+#[cfg(feature = "alloc")]
+use alloc::ffi as alloc_ffi;
+
+use CStr;
+#[cfg(feature = "alloc")]
+use CString;
+
+impl<'a> From<&'a ffi::CStr> for &'a CStr {
+    #[inline]
+    fn from(s: &'a ffi::CStr) -> &'a CStr {
+        unsafe { CStr::from_bytes_with_nul_unchecked(s.to_bytes_with_nul()) }
+    }
+}
+
+impl<'a> From<&'a CStr> for &'a ffi::CStr {
+    #[inline]
+    fn from(s: &'a CStr) -> &'a ffi::CStr {
+        unsafe { ffi::CStr::from_bytes_with_nul_unchecked(s.to_bytes_with_nul()) }
+    }
+}
+
+impl AsRef<ffi::CStr> for CStr {
+    #[inline]
+    fn as_ref(&self) -> &ffi::CStr {
+        self.into()
+    }
+}
+
+impl AsRef<CStr> for ffi::CStr {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.into()
+    }
+}
+
+impl PartialEq<ffi::CStr> for CStr {
+    #[inline]
+    fn eq(&self, other: &ffi::CStr) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl PartialEq<CStr> for ffi::CStr {
+    #[inline]
+    fn eq(&self, other: &CStr) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<ffi::CStr> for CString {
+    #[inline]
+    fn as_ref(&self) -> &ffi::CStr {
+        self.as_c_str().into()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<CStr> for alloc_ffi::CString {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str().into()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<CString> for alloc_ffi::CString {
+    #[inline]
+    fn from(s: CString) -> alloc_ffi::CString {
+        // The vector is created from a boxed slice, so its length equals its
+        // capacity and converting it back into a box does not reallocate.
+        unsafe { alloc_ffi::CString::from_vec_with_nul_unchecked(s.into_bytes_with_nul()) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<alloc_ffi::CString> for CString {
+    #[inline]
+    fn from(s: alloc_ffi::CString) -> CString {
+        CString {
+            inner: s.into_bytes_with_nul().into_boxed_slice(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq<alloc_ffi::CString> for CString {
+    #[inline]
+    fn eq(&self, other: &alloc_ffi::CString) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq<CString> for alloc_ffi::CString {
+    #[inline]
+    fn eq(&self, other: &CString) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrowed() {
+        let core_str = ffi::CStr::from_bytes_with_nul(b"hello\0").unwrap();
+        let ours: &CStr = core_str.into();
+        assert_eq!(ours.as_ptr(), core_str.as_ptr());
+        assert_eq!(ours, core_str);
+        assert_eq!(core_str, ours);
+
+        let back: &ffi::CStr = ours.into();
+        assert_eq!(back, core_str);
+        assert_eq!(AsRef::<ffi::CStr>::as_ref(ours), core_str);
+        assert_eq!(AsRef::<CStr>::as_ref(core_str), ours);
+    }
+
+    #[test]
+    fn owned() {
+        let ours = CString::new("hello").unwrap();
+        let ptr = ours.as_ptr();
+        let theirs = alloc_ffi::CString::from(ours);
+        assert_eq!(theirs.as_ptr(), ptr);
+        assert_eq!(theirs.as_bytes(), b"hello");
+
+        let ours = CString::from(theirs);
+        assert_eq!(ours.as_ptr(), ptr);
+        assert_eq!(ours, alloc_ffi::CString::new("hello").unwrap());
+    }
+}
@@ -0,0 +1,365 @@
+use alloc::boxed::Box as GlobalBox;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ptr::{self, NonNull};
+use core::{fmt, ops};
+
+use allocator_api2::alloc::{Allocator, Global};
+use allocator_api2::boxed::Box;
+use allocator_api2::vec::Vec;
+use memchr::memchr;
+
+use {c_char, CStr, CString};
+
+/// An owned C string allocated with a custom allocator.
+///
+/// This is the counterpart of [`CString`] for the [`Allocator`] trait of the
+/// `allocator-api2` crate, for strings which must come from a specific memory
+/// pool. `CStringIn<Global>` converts to and from [`CString`] without copying.
+///
+/// [`CString`]: struct.CString.html
+/// [`Allocator`]: https://docs.rs/allocator-api2/0.2/allocator_api2/alloc/trait.Allocator.html
+///
+/// # Examples
+///
+/// ```
+/// extern crate allocator_api2;
+/// # extern crate cstr_core;
+///
+/// use allocator_api2::alloc::Global;
+/// use cstr_core::{CString, CStringIn};
+///
+/// let s = CStringIn::new_in(b"pool", Global).unwrap();
+/// assert_eq!(s.to_bytes(), b"pool");
+///
+/// let c: CString = s.into();
+/// assert_eq!(c.as_bytes(), b"pool");
+/// ```
+pub struct CStringIn<A: Allocator = Global> {
+    // Invariant 1: the slice ends with a zero byte and has a length of at
+    //              least one.
+    // Invariant 2: the slice contains only one zero byte.
+    inner: Box<[u8], A>,
+}
+
+/// An error returned from [`CStringIn::from_vec_in`] to indicate that a nul
+/// byte was found in the vector provided.
+///
+/// This is the counterpart of [`NulError`], which gives the vector back
+/// without reallocating it with the global allocator.
+///
+/// [`CStringIn::from_vec_in`]: struct.CStringIn.html#method.from_vec_in
+/// [`NulError`]: struct.NulError.html
+#[derive(Clone, PartialEq, Eq)]
+pub struct NulErrorIn<A: Allocator>(usize, Vec<u8, A>);
+
+impl<A: Allocator> NulErrorIn<A> {
+    /// Returns the position of the nul byte in the slice that caused the
+    /// conversion to fail.
+    #[inline]
+    pub fn nul_position(&self) -> usize {
+        self.0
+    }
+
+    /// Consumes this error, returning the underlying vector of bytes which
+    /// generated the error in the first place.
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8, A> {
+        self.1
+    }
+}
+
+impl<A: Allocator> fmt::Debug for NulErrorIn<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("NulErrorIn")
+            .field(&self.0)
+            .field(&&self.1[..])
+            .finish()
+    }
+}
+
+impl<A: Allocator> fmt::Display for NulErrorIn<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "nul byte found in provided data at position: {}", self.0)
+    }
+}
+
+impl<A: Allocator> ::core::error::Error for NulErrorIn<A> {}
+
+impl<A: Allocator> CStringIn<A> {
+    /// Creates a C string by copying the given bytes into memory from
+    /// `alloc`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NulErrorIn`] if the bytes contain a nul byte.
+    ///
+    /// [`NulErrorIn`]: struct.NulErrorIn.html
+    pub fn new_in<T: AsRef<[u8]>>(t: T, alloc: A) -> Result<CStringIn<A>, NulErrorIn<A>> {
+        let bytes = t.as_ref();
+        let mut v = Vec::with_capacity_in(bytes.len() + 1, alloc);
+        v.extend_from_slice(bytes);
+        CStringIn::from_vec_in(v)
+    }
+
+    /// Creates a C string from a vector of bytes, reusing its allocation.
+    ///
+    /// A nul terminator is appended, which may reallocate the vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NulErrorIn`] holding the vector if it contains a nul byte.
+    ///
+    /// [`NulErrorIn`]: struct.NulErrorIn.html
+    pub fn from_vec_in(v: Vec<u8, A>) -> Result<CStringIn<A>, NulErrorIn<A>> {
+        match memchr(0, &v) {
+            Some(i) => Err(NulErrorIn(i, v)),
+            None => Ok(unsafe { CStringIn::from_vec_unchecked_in(v) }),
+        }
+    }
+
+    /// Creates a C string from a vector of bytes without checking for
+    /// interior nul bytes.
+    ///
+    /// # Safety
+    ///
+    /// `v` must not contain any nul byte.
+    pub unsafe fn from_vec_unchecked_in(mut v: Vec<u8, A>) -> CStringIn<A> {
+        v.reserve_exact(1);
+        v.push(0);
+        CStringIn {
+            inner: v.into_boxed_slice(),
+        }
+    }
+
+    /// Decomposes the string into a pointer to its bytes, their number
+    /// including the nul terminator, and its allocator.
+    ///
+    /// The string can be rebuilt with [`from_raw_parts`].
+    ///
+    /// [`from_raw_parts`]: #method.from_raw_parts
+    #[inline]
+    pub fn into_raw_parts(self) -> (NonNull<c_char>, usize, A) {
+        let len = self.inner.len();
+        let (ptr, alloc) = Box::into_raw_with_allocator(self.inner);
+        let ptr = unsafe { NonNull::new_unchecked(ptr as *mut c_char) };
+        (ptr, len, alloc)
+    }
+
+    /// Rebuilds a string from the parts returned by
+    /// [`into_raw_parts`](#method.into_raw_parts).
+    ///
+    /// # Safety
+    ///
+    /// The parts must come from a call to `into_raw_parts`, and the bytes
+    /// must not have been modified in a way that breaks the invariants of a C
+    /// string.
+    #[inline]
+    pub unsafe fn from_raw_parts(ptr: NonNull<c_char>, len: usize, alloc: A) -> CStringIn<A> {
+        let slice = ptr::slice_from_raw_parts_mut(ptr.as_ptr() as *mut u8, len);
+        CStringIn {
+            inner: Box::from_raw_in(slice, alloc),
+        }
+    }
+
+    /// Returns a reference to the allocator of the string.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        Box::allocator(&self.inner)
+    }
+
+    /// Extracts a [`CStr`] slice containing the entire string.
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.inner) }
+    }
+
+    /// Converts the string into its bytes, without the nul terminator.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8, A> {
+        let mut vec = self.into_bytes_with_nul();
+        let _nul = vec.pop();
+        debug_assert_eq!(_nul, Some(0u8));
+        vec
+    }
+
+    /// Converts the string into its bytes, including the nul terminator.
+    #[inline]
+    pub fn into_bytes_with_nul(self) -> Vec<u8, A> {
+        self.inner.into_vec()
+    }
+}
+
+impl<A: Allocator> ops::Deref for CStringIn<A> {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl<A: Allocator> AsRef<CStr> for CStringIn<A> {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl<A: Allocator> Borrow<CStr> for CStringIn<A> {
+    #[inline]
+    fn borrow(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl<A: Allocator + Clone> Clone for CStringIn<A> {
+    #[inline]
+    fn clone(&self) -> CStringIn<A> {
+        CStringIn {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<A: Allocator> fmt::Debug for CStringIn<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl<A: Allocator, B: Allocator> PartialEq<CStringIn<B>> for CStringIn<A> {
+    #[inline]
+    fn eq(&self, other: &CStringIn<B>) -> bool {
+        self.as_c_str() == other.as_c_str()
+    }
+}
+
+impl<A: Allocator> Eq for CStringIn<A> {}
+
+impl<A: Allocator> PartialOrd for CStringIn<A> {
+    #[inline]
+    fn partial_cmp(&self, other: &CStringIn<A>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Allocator> Ord for CStringIn<A> {
+    #[inline]
+    fn cmp(&self, other: &CStringIn<A>) -> Ordering {
+        self.as_c_str().cmp(other.as_c_str())
+    }
+}
+
+impl<A: Allocator> Hash for CStringIn<A> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_c_str().hash(state)
+    }
+}
+
+impl<'a> From<&'a CStr> for CStringIn<Global> {
+    #[inline]
+    fn from(s: &'a CStr) -> CStringIn<Global> {
+        CStringIn {
+            inner: Box::from(s.to_bytes_with_nul()),
+        }
+    }
+}
+
+impl From<CString> for CStringIn<Global> {
+    #[inline]
+    fn from(s: CString) -> CStringIn<Global> {
+        let raw = GlobalBox::into_raw(s.into_bytes_with_nul().into_boxed_slice());
+        // `Global` allocates with the global allocator, like `alloc::boxed::Box`.
+        CStringIn {
+            inner: unsafe { Box::from_raw_in(raw, Global) },
+        }
+    }
+}
+
+impl From<CStringIn<Global>> for CString {
+    #[inline]
+    fn from(s: CStringIn<Global>) -> CString {
+        let (raw, _) = Box::into_raw_with_allocator(s.inner);
+        CString {
+            inner: unsafe { GlobalBox::from_raw(raw) },
+        }
+    }
+}
+
+impl PartialEq<CString> for CStringIn<Global> {
+    #[inline]
+    fn eq(&self, other: &CString) -> bool {
+        self.as_c_str() == other.as_c_str()
+    }
+}
+
+impl PartialEq<CStringIn<Global>> for CString {
+    #[inline]
+    fn eq(&self, other: &CStringIn<Global>) -> bool {
+        self.as_c_str() == other.as_c_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator_api2::alloc::AllocError;
+    use core::alloc::Layout;
+    use core::cell::Cell;
+
+    /// Counts the allocations made through it.
+    #[derive(Clone, Copy)]
+    struct Counting<'a>(&'a Cell<usize>);
+
+    unsafe impl<'a> Allocator for Counting<'a> {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.0.set(self.0.get() - 1);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn custom_allocator() {
+        let live = Cell::new(0);
+        let s = CStringIn::new_in("abc", Counting(&live)).unwrap();
+        assert_eq!(live.get(), 1);
+        assert_eq!(s.to_bytes_with_nul(), b"abc\0");
+        assert_eq!(s.clone(), s);
+        assert_eq!(live.get(), 1);
+
+        let (ptr, len, alloc) = s.into_raw_parts();
+        assert_eq!(len, 4);
+        let s = unsafe { CStringIn::from_raw_parts(ptr, len, alloc) };
+        assert_eq!(s.as_ptr(), ptr.as_ptr() as *const c_char);
+        assert_eq!(s.into_bytes().as_slice(), b"abc");
+        assert_eq!(live.get(), 0);
+
+        let mut v = Vec::new_in(Counting(&live));
+        v.extend_from_slice(b"a\0b");
+        let err = CStringIn::from_vec_in(v).unwrap_err();
+        assert_eq!(err.nul_position(), 1);
+        assert_eq!(err.into_vec().as_slice(), b"a\0b");
+        assert_eq!(live.get(), 0);
+    }
+
+    #[test]
+    fn global() {
+        let c = CString::new("global").unwrap();
+        let ptr = c.as_ptr();
+        let s = CStringIn::from(c);
+        assert_eq!(s.as_ptr(), ptr);
+        let c = CString::from(s);
+        assert_eq!(c.as_ptr(), ptr);
+        assert_eq!(CStringIn::from(c.as_c_str()), c);
+    }
+}
@@ -0,0 +1,344 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::{FromIterator, FusedIterator};
+use core::marker::PhantomData;
+use core::{fmt, ops, ptr, slice};
+
+use {c_char, CStr};
+
+/// A list of C strings stored in a single buffer.
+///
+/// A `Vec<CString>` makes one heap allocation per string. This type instead
+/// packs all strings, with their nul terminators, into one `Vec<u8>` along
+/// with an index of where each of them starts. Reordering the list, for
+/// example by [`sort`], only moves the index.
+///
+/// [`sort`]: #method.sort
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{CStr, CString, CStringVec};
+///
+/// let mut args: CStringVec = ["ls", "-l"]
+///     .iter()
+///     .map(|&s| CString::new(s).unwrap())
+///     .collect();
+/// args.push(CStr::from_bytes_with_nul(b"/tmp\0").unwrap());
+/// assert_eq!(args.len(), 3);
+/// assert_eq!(args[2].to_bytes(), b"/tmp");
+/// ```
+#[derive(Clone, Default)]
+pub struct CStringVec {
+    bytes: Vec<u8>,
+    /// Start and length, with the nul terminator, of each string in `bytes`.
+    spans: Vec<(usize, usize)>,
+}
+
+#[inline]
+fn span(bytes: &[u8], (start, len): (usize, usize)) -> &CStr {
+    unsafe { CStr::from_bytes_with_nul_unchecked(&bytes[start..start + len]) }
+}
+
+impl CStringVec {
+    /// Creates an empty list.
+    #[inline]
+    pub fn new() -> CStringVec {
+        CStringVec::default()
+    }
+
+    /// Creates an empty list with room for `strings` strings totalling
+    /// `bytes` bytes, including their nul terminators.
+    #[inline]
+    pub fn with_capacity(strings: usize, bytes: usize) -> CStringVec {
+        CStringVec {
+            bytes: Vec::with_capacity(bytes),
+            spans: Vec::with_capacity(strings),
+        }
+    }
+
+    /// Returns the number of strings in the list.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns whether the list is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Appends a copy of a string to the list.
+    pub fn push(&mut self, s: &CStr) {
+        let bytes = s.to_bytes_with_nul();
+        self.spans.push((self.bytes.len(), bytes.len()));
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Returns the string at `index`, or `None` if it is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&CStr> {
+        self.spans.get(index).map(|&s| span(&self.bytes, s))
+    }
+
+    /// Removes all strings from the list.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.spans.clear();
+    }
+
+    /// Returns an iterator over the strings of the list.
+    #[inline]
+    pub fn iter(&self) -> CStringVecIter<'_> {
+        CStringVecIter {
+            bytes: &self.bytes,
+            spans: self.spans.iter(),
+        }
+    }
+
+    /// Sorts the list.
+    ///
+    /// The sort is stable, and does not move the bytes of the strings.
+    #[inline]
+    pub fn sort(&mut self) {
+        self.sort_by(Ord::cmp)
+    }
+
+    /// Sorts the list with a comparator function.
+    ///
+    /// The sort is stable, and does not move the bytes of the strings.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&CStr, &CStr) -> Ordering,
+    {
+        let bytes = &self.bytes;
+        self.spans
+            .sort_by(|&a, &b| compare(span(bytes, a), span(bytes, b)));
+    }
+
+    /// Returns the buffer holding the strings.
+    ///
+    /// The strings are stored in the order they were pushed, regardless of
+    /// any later reordering of the list.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns a null-terminated array of pointers to the strings, such as
+    /// the `argv` and `envp` arguments of `execve`.
+    ///
+    /// This allocates the array of pointers, but does not copy the strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{CStr, CStringVec};
+    ///
+    /// let mut argv = CStringVec::new();
+    /// argv.push(CStr::from_bytes_with_nul(b"true\0").unwrap());
+    /// let ptrs = argv.to_ptr_array();
+    /// assert_eq!(ptrs.len(), 1);
+    /// unsafe {
+    ///     assert_eq!(CStr::from_ptr(*ptrs.as_ptr()).to_bytes(), b"true");
+    ///     assert!((*ptrs.as_ptr().add(1)).is_null());
+    /// }
+    /// ```
+    pub fn to_ptr_array(&self) -> CStrPtrArray<'_> {
+        let mut ptrs = Vec::with_capacity(self.spans.len() + 1);
+        ptrs.extend(self.iter().map(CStr::as_ptr));
+        ptrs.push(ptr::null());
+        CStrPtrArray {
+            ptrs,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl ops::Index<usize> for CStringVec {
+    type Output = CStr;
+
+    #[inline]
+    fn index(&self, index: usize) -> &CStr {
+        span(&self.bytes, self.spans[index])
+    }
+}
+
+impl PartialEq for CStringVec {
+    #[inline]
+    fn eq(&self, other: &CStringVec) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for CStringVec {}
+
+impl fmt::Debug for CStringVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: AsRef<CStr>> Extend<T> for CStringVec {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for s in iter {
+            self.push(s.as_ref());
+        }
+    }
+}
+
+impl<T: AsRef<CStr>> FromIterator<T> for CStringVec {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> CStringVec {
+        let mut vec = CStringVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<'a> IntoIterator for &'a CStringVec {
+    type Item = &'a CStr;
+    type IntoIter = CStringVecIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> CStringVecIter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the strings of a [`CStringVec`].
+///
+/// This `struct` is created by [`CStringVec::iter`].
+///
+/// [`CStringVec`]: struct.CStringVec.html
+/// [`CStringVec::iter`]: struct.CStringVec.html#method.iter
+#[derive(Clone, Debug)]
+pub struct CStringVecIter<'a> {
+    bytes: &'a [u8],
+    spans: slice::Iter<'a, (usize, usize)>,
+}
+
+impl<'a> Iterator for CStringVecIter<'a> {
+    type Item = &'a CStr;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a CStr> {
+        self.spans.next().map(|&s| span(self.bytes, s))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spans.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for CStringVecIter<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a CStr> {
+        self.spans.next_back().map(|&s| span(self.bytes, s))
+    }
+}
+
+impl<'a> ExactSizeIterator for CStringVecIter<'a> {}
+
+impl<'a> FusedIterator for CStringVecIter<'a> {}
+
+/// A null-terminated array of pointers to the strings of a [`CStringVec`].
+///
+/// This `struct` is created by [`CStringVec::to_ptr_array`]. The pointers
+/// borrow from the list, which cannot be modified while the array exists.
+///
+/// [`CStringVec`]: struct.CStringVec.html
+/// [`CStringVec::to_ptr_array`]: struct.CStringVec.html#method.to_ptr_array
+#[derive(Debug)]
+pub struct CStrPtrArray<'a> {
+    ptrs: Vec<*const c_char>,
+    _marker: PhantomData<&'a CStr>,
+}
+
+impl<'a> CStrPtrArray<'a> {
+    /// Returns a pointer to the first element of the array, which is
+    /// terminated by a null pointer.
+    #[inline]
+    pub fn as_ptr(&self) -> *const *const c_char {
+        self.ptrs.as_ptr()
+    }
+
+    /// Returns the number of strings in the array, not counting the
+    /// terminating null pointer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ptrs.len() - 1
+    }
+
+    /// Returns whether the array holds no string.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn c(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    fn to_vec(vec: &CStringVec) -> Vec<&[u8]> {
+        vec.iter().map(CStr::to_bytes).collect()
+    }
+
+    #[test]
+    fn push_get() {
+        let mut vec = CStringVec::with_capacity(3, 8);
+        assert!(vec.is_empty());
+        vec.push(c(b"ab\0"));
+        vec.push(c(b"\0"));
+        vec.push(c(b"cde\0"));
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.as_bytes(), b"ab\0\0cde\0");
+        assert_eq!(vec.get(0), Some(c(b"ab\0")));
+        assert_eq!(&vec[1], c(b"\0"));
+        assert_eq!(vec.get(3), None);
+        assert_eq!(to_vec(&vec), [&b"ab"[..], b"", b"cde"]);
+        assert_eq!(vec.iter().next_back(), Some(c(b"cde\0")));
+        assert_eq!(format!("{:?}", vec), r#"["ab", "", "cde"]"#);
+
+        vec.clear();
+        assert!(vec.is_empty());
+        assert!(vec.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn sort() {
+        let mut vec: CStringVec = [&b"b\0"[..], b"c\0", b"a\0"].iter().map(|b| c(b)).collect();
+        let unsorted = vec.clone();
+        vec.sort();
+        assert_eq!(to_vec(&vec), [b"a", b"b", b"c"]);
+        assert_eq!(vec.as_bytes(), b"b\0c\0a\0");
+        assert_ne!(vec, unsorted);
+
+        vec.sort_by(|a, b| b.cmp(a));
+        assert_eq!(to_vec(&vec), [b"c", b"b", b"a"]);
+        let sorted: CStringVec = vec.iter().collect();
+        assert_eq!(vec, sorted);
+    }
+
+    #[test]
+    fn ptr_array() {
+        let vec: CStringVec = [c(b"x\0"), c(b"yz\0")].iter().collect();
+        let ptrs = vec.to_ptr_array();
+        assert_eq!(ptrs.len(), 2);
+        unsafe {
+            let p = ptrs.as_ptr();
+            assert_eq!(CStr::from_ptr(*p), c(b"x\0"));
+            assert_eq!(CStr::from_ptr(*p.add(1)), c(b"yz\0"));
+            assert!((*p.add(2)).is_null());
+        }
+        assert!(CStringVec::new().to_ptr_array().is_empty());
+    }
+}
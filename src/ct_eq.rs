@@ -0,0 +1,88 @@
+use core::hint;
+
+#[cfg(feature = "subtle")]
+use subtle::{Choice, ConstantTimeEq};
+
+use CStr;
+#[cfg(all(feature = "subtle", feature = "alloc"))]
+use CString;
+
+impl CStr {
+    /// Compares two strings in time depending only on their lengths.
+    ///
+    /// Unlike `==`, this does not stop at the first differing byte, so it
+    /// can be used to compare secrets such as tokens or passwords without
+    /// revealing how long a matching prefix is. Strings of different lengths
+    /// compare unequal immediately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::CStr;
+    ///
+    /// let a = CStr::from_bytes_with_nul(b"token\0").unwrap();
+    /// let b = CStr::from_bytes_with_nul(b"tokem\0").unwrap();
+    /// assert!(a.ct_eq(a));
+    /// assert!(!a.ct_eq(b));
+    /// ```
+    pub fn ct_eq(&self, other: &CStr) -> bool {
+        let a = self.to_bytes();
+        let b = other.to_bytes();
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut diff = 0;
+        for (x, y) in a.iter().zip(b) {
+            diff |= x ^ y;
+        }
+        hint::black_box(diff) == 0
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl ConstantTimeEq for CStr {
+    #[inline]
+    fn ct_eq(&self, other: &CStr) -> Choice {
+        self.to_bytes().ct_eq(other.to_bytes())
+    }
+}
+
+#[cfg(all(feature = "subtle", feature = "alloc"))]
+impl ConstantTimeEq for CString {
+    #[inline]
+    fn ct_eq(&self, other: &CString) -> Choice {
+        self.to_bytes().ct_eq(other.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ct_eq() {
+        let a = CStr::from_bytes_with_nul(b"abc\0").unwrap();
+        let b = CStr::from_bytes_with_nul(b"abd\0").unwrap();
+        let c = CStr::from_bytes_with_nul(b"ab\0").unwrap();
+        assert!(a.ct_eq(a));
+        assert!(!a.ct_eq(b));
+        assert!(!a.ct_eq(c));
+        assert!(!c.ct_eq(a));
+    }
+
+    #[cfg(feature = "subtle")]
+    #[test]
+    fn subtle() {
+        let a = CStr::from_bytes_with_nul(b"abc\0").unwrap();
+        let b = CStr::from_bytes_with_nul(b"ab\0").unwrap();
+        assert!(bool::from(ConstantTimeEq::ct_eq(a, a)));
+        assert!(!bool::from(ConstantTimeEq::ct_eq(a, b)));
+
+        #[cfg(feature = "alloc")]
+        {
+            let s = CString::from(a);
+            assert!(bool::from(s.ct_eq(&s.clone())));
+            assert!(!bool::from(s.ct_eq(&CString::from(b))));
+        }
+    }
+}
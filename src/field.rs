@@ -0,0 +1,288 @@
+use core::fmt::{self, Write};
+use core::hash::{Hash, Hasher};
+use core::{ptr, slice};
+
+use memchr::memchr;
+
+use copy::BufferTooSmallError;
+use {ascii, c_char, CStr};
+
+/// A fixed-width `char name[N]` field, as found in `repr(C)` structures.
+///
+/// Such fields are common in kernel ABIs and file formats (`utsname`, tar
+/// headers, ...) and are not always nul-terminated: a string may fill the whole
+/// field, and some formats pad with spaces instead of nul bytes. This type has
+/// the same layout as `[c_char; N]` and gives safe access to its contents.
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{CStr, CStrField};
+///
+/// #[repr(C)]
+/// struct Header {
+///     name: CStrField<8>,
+///     mode: CStrField<4>,
+/// }
+///
+/// let mut header = Header { name: CStrField::new(), mode: CStrField::new() };
+/// header.name.set(CStr::from_bytes_with_nul(b"foo\0").unwrap()).unwrap();
+/// header.mode.set_space_padded(b"644").unwrap();
+///
+/// assert_eq!(header.name.as_c_str().unwrap().to_bytes(), b"foo");
+/// assert_eq!(header.mode.as_bytes(), b"644 ");
+/// assert_eq!(header.mode.as_bytes_trimmed(), b"644");
+/// ```
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct CStrField<const N: usize>([c_char; N]);
+
+impl<const N: usize> CStrField<N> {
+    /// Creates a field filled with nul bytes.
+    #[inline]
+    pub const fn new() -> CStrField<N> {
+        CStrField([0; N])
+    }
+
+    /// Wraps a raw array.
+    #[inline]
+    pub const fn from_array(array: [c_char; N]) -> CStrField<N> {
+        CStrField(array)
+    }
+
+    /// Returns the raw array.
+    #[inline]
+    pub const fn into_array(self) -> [c_char; N] {
+        self.0
+    }
+
+    /// Returns a reference to the raw array.
+    #[inline]
+    pub fn as_array(&self) -> &[c_char; N] {
+        &self.0
+    }
+
+    /// Returns a mutable reference to the raw array.
+    #[inline]
+    pub fn as_mut_array(&mut self) -> &mut [c_char; N] {
+        &mut self.0
+    }
+
+    /// Returns all `N` bytes of the field.
+    #[inline]
+    fn raw_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.0.as_ptr() as *const u8, N) }
+    }
+
+    /// Returns the contents of the field as a C string, or `None` if it holds
+    /// no nul terminator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{c_char, CStrField};
+    ///
+    /// let field = CStrField::from_array([b'a' as c_char, 0, b'b' as c_char]);
+    /// assert_eq!(field.as_c_str().unwrap().to_bytes(), b"a");
+    ///
+    /// let field = CStrField::from_array([b'a' as c_char; 3]);
+    /// assert!(field.as_c_str().is_none());
+    /// ```
+    #[inline]
+    pub fn as_c_str(&self) -> Option<&CStr> {
+        let bytes = self.raw_bytes();
+        memchr(0, bytes)
+            .map(|nul| unsafe { CStr::from_bytes_with_nul_unchecked(&bytes[..nul + 1]) })
+    }
+
+    /// Returns the bytes of the field up to the first nul byte, or all `N`
+    /// bytes if there is none.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        let bytes = self.raw_bytes();
+        match memchr(0, bytes) {
+            Some(nul) => &bytes[..nul],
+            None => bytes,
+        }
+    }
+
+    /// Returns the same bytes as [`as_bytes`], without trailing spaces.
+    ///
+    /// This is meant for fields which are padded with spaces rather than nul
+    /// bytes.
+    ///
+    /// [`as_bytes`]: #method.as_bytes
+    #[inline]
+    pub fn as_bytes_trimmed(&self) -> &[u8] {
+        let bytes = self.as_bytes();
+        let len = bytes.iter().rposition(|&b| b != b' ').map_or(0, |i| i + 1);
+        &bytes[..len]
+    }
+
+    /// Stores a C string in the field, padding the rest of it with nul
+    /// bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BufferTooSmallError`] if the string and its nul terminator
+    /// do not fit in the field, which is left untouched in that case.
+    ///
+    /// [`BufferTooSmallError`]: struct.BufferTooSmallError.html
+    pub fn set(&mut self, s: &CStr) -> Result<(), BufferTooSmallError> {
+        let bytes = s.to_bytes();
+        if bytes.len() >= N {
+            return Err(BufferTooSmallError::new(bytes.len() + 1, N));
+        }
+        self.fill(bytes, 0);
+        Ok(())
+    }
+
+    /// Stores bytes in the field, padding the rest of it with spaces.
+    ///
+    /// No nul terminator is written, so `bytes` may fill the whole field. If
+    /// `bytes` contains a nul byte, only the bytes before it are stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BufferTooSmallError`] if `bytes` is longer than the field,
+    /// which is left untouched in that case.
+    ///
+    /// [`BufferTooSmallError`]: struct.BufferTooSmallError.html
+    pub fn set_space_padded(&mut self, bytes: &[u8]) -> Result<(), BufferTooSmallError> {
+        let bytes = match memchr(0, bytes) {
+            Some(nul) => &bytes[..nul],
+            None => bytes,
+        };
+        if bytes.len() > N {
+            return Err(BufferTooSmallError::new(bytes.len(), N));
+        }
+        self.fill(bytes, b' ');
+        Ok(())
+    }
+
+    fn fill(&mut self, bytes: &[u8], pad: u8) {
+        unsafe {
+            ptr::copy_nonoverlapping(
+                bytes.as_ptr() as *const c_char,
+                self.0.as_mut_ptr(),
+                bytes.len(),
+            );
+        }
+        for c in &mut self.0[bytes.len()..] {
+            *c = pad as c_char;
+        }
+    }
+}
+
+impl<const N: usize> Default for CStrField<N> {
+    #[inline]
+    fn default() -> CStrField<N> {
+        CStrField::new()
+    }
+}
+
+impl<const N: usize> From<[c_char; N]> for CStrField<N> {
+    #[inline]
+    fn from(array: [c_char; N]) -> CStrField<N> {
+        CStrField(array)
+    }
+}
+
+/// Compares the bytes returned by [`as_bytes`], ignoring anything after the
+/// nul terminator.
+///
+/// [`as_bytes`]: struct.CStrField.html#method.as_bytes
+impl<const N: usize> PartialEq for CStrField<N> {
+    #[inline]
+    fn eq(&self, other: &CStrField<N>) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> Eq for CStrField<N> {}
+
+impl<const N: usize> Hash for CStrField<N> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl<const N: usize> fmt::Debug for CStrField<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+        for byte in self
+            .as_bytes()
+            .iter()
+            .flat_map(|&b| ascii::escape_default(b))
+        {
+            f.write_char(byte as char)?;
+        }
+        f.write_char('"')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem;
+
+    fn c(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(mem::size_of::<CStrField<65>>(), 65);
+        assert_eq!(mem::align_of::<CStrField<65>>(), 1);
+    }
+
+    #[test]
+    fn set() {
+        let mut f = CStrField::<4>::from_array([1; 4]);
+        f.set(c(b"ab\0")).unwrap();
+        assert_eq!(f.as_array(), &[b'a' as c_char, b'b' as c_char, 0, 0]);
+        assert_eq!(f.as_c_str(), Some(c(b"ab\0")));
+        assert_eq!(f.as_bytes(), b"ab");
+
+        let err = f.set(c(b"abcd\0")).unwrap_err();
+        assert_eq!((err.required(), err.available()), (5, 4));
+        assert_eq!(f.as_bytes(), b"ab");
+
+        f.set(c(b"\0")).unwrap();
+        assert_eq!(f, CStrField::new());
+    }
+
+    #[test]
+    fn unterminated() {
+        let mut f = CStrField::<4>::new();
+        f.set_space_padded(b"abcd").unwrap();
+        assert_eq!(f.as_c_str(), None);
+        assert_eq!(f.as_bytes(), b"abcd");
+        assert!(f.set_space_padded(b"abcde").is_err());
+
+        f.set_space_padded(b"a").unwrap();
+        assert_eq!(f.as_bytes(), b"a   ");
+        assert_eq!(f.as_bytes_trimmed(), b"a");
+
+        f.set_space_padded(b"").unwrap();
+        assert_eq!(f.as_bytes_trimmed(), b"");
+        assert_eq!(format!("{:?}", f), r#""    ""#);
+    }
+
+    #[test]
+    fn eq_ignores_trailing_bytes() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |f: &CStrField<4>| {
+            let mut h = DefaultHasher::new();
+            f.hash(&mut h);
+            h.finish()
+        };
+        let a = CStrField::from_array([b'a' as c_char, 0, 1, 2]);
+        let b = CStrField::from_array([b'a' as c_char, 0, 0, 0]);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert!(a != CStrField::from_array([b'a' as c_char; 4]));
+    }
+}
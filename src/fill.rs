@@ -0,0 +1,255 @@
+use core::error::Error;
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use c_char;
+#[cfg(feature = "alloc")]
+use CString;
+
+/// The status reported by the callback of [`CString::from_ffi_fill_growing`].
+///
+/// [`CString::from_ffi_fill_growing`]: struct.CString.html#method.from_ffi_fill_growing
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FillStatus<E> {
+    /// The buffer now holds a nul-terminated string.
+    Done,
+    /// The buffer was too small, e.g. the function failed with `ERANGE`.
+    TooSmall,
+    /// The function failed for another reason.
+    Failed(E),
+}
+
+/// An error returned when filling a buffer through a foreign function fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FillError<E> {
+    /// The callback returned successfully but did not write a nul terminator
+    /// within the buffer.
+    NotNulTerminated,
+    /// The buffer was still too small at the maximum capacity.
+    TooLarge,
+    /// The callback reported an error.
+    Failed(E),
+}
+
+impl<E: fmt::Display> fmt::Display for FillError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FillError::NotNulTerminated => {
+                f.write_str("buffer was filled without a nul terminator")
+            }
+            FillError::TooLarge => {
+                f.write_str("string does not fit in the maximum buffer capacity")
+            }
+            FillError::Failed(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> Error for FillError<E> {}
+
+/// Returns the length of the nul-terminated string at `ptr`, without looking
+/// at more than `cap` bytes.
+///
+/// The bytes up to and including the first nul, or all `cap` bytes if there is
+/// none, must be initialized. This is why the bytes are read one at a time
+/// rather than with `memchr`, which would read the uninitialized bytes after
+/// the nul.
+#[inline]
+pub(crate) unsafe fn strnlen(ptr: *const u8, cap: usize) -> Option<usize> {
+    (0..cap).find(|&i| *ptr.add(i) == 0)
+}
+
+#[cfg(feature = "alloc")]
+impl CString {
+    /// Creates a `CString` by letting a foreign function write into a buffer.
+    ///
+    /// This allocates an uninitialized buffer of `capacity` bytes and calls
+    /// `f` with a pointer to it and its length, in the style of C functions
+    /// taking a `char *buf, size_t len` pair such as `gethostname`. If `f`
+    /// succeeds, the string is everything up to the first nul byte in the
+    /// buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FillError::Failed`] if `f` returns an error, and
+    /// [`FillError::NotNulTerminated`] if the buffer holds no nul byte.
+    ///
+    /// [`FillError::Failed`]: enum.FillError.html#variant.Failed
+    /// [`FillError::NotNulTerminated`]: enum.FillError.html#variant.NotNulTerminated
+    ///
+    /// # Safety
+    ///
+    /// If `f` returns `Ok`, it must have initialized the buffer up to and
+    /// including a nul byte, or in its entirety.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{c_char, CString};
+    ///
+    /// unsafe fn fill(buf: *mut c_char, len: usize) -> Result<(), ()> {
+    ///     if len < 4 {
+    ///         return Err(());
+    ///     }
+    ///     std::ptr::copy_nonoverlapping(b"foo\0".as_ptr() as *const c_char, buf, 4);
+    ///     Ok(())
+    /// }
+    ///
+    /// let s = unsafe { CString::from_ffi_fill(64, |buf, len| fill(buf, len)) }.unwrap();
+    /// assert_eq!(s.as_bytes(), b"foo");
+    /// ```
+    pub unsafe fn from_ffi_fill<F, E>(capacity: usize, f: F) -> Result<CString, FillError<E>>
+    where
+        F: FnOnce(*mut c_char, usize) -> Result<(), E>,
+    {
+        let mut v = Vec::with_capacity(capacity);
+        f(v.as_mut_ptr() as *mut c_char, capacity).map_err(FillError::Failed)?;
+        match strnlen(v.as_ptr(), capacity) {
+            Some(len) => {
+                v.set_len(len);
+                Ok(CString::from_vec_unchecked(v))
+            }
+            None => Err(FillError::NotNulTerminated),
+        }
+    }
+
+    /// Creates a `CString` by letting a foreign function write into a buffer,
+    /// growing the buffer until the string fits.
+    ///
+    /// This is like [`from_ffi_fill`], except that the buffer capacity starts
+    /// at `initial_capacity` and is doubled, up to `max_capacity`, whenever `f`
+    /// returns [`FillStatus::TooSmall`] or leaves the buffer without a nul
+    /// terminator. This suits functions such as `strerror_r` which signal a
+    /// short buffer with `ERANGE`.
+    ///
+    /// [`from_ffi_fill`]: #method.from_ffi_fill
+    /// [`FillStatus::TooSmall`]: enum.FillStatus.html#variant.TooSmall
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FillError::Failed`] if `f` returns [`FillStatus::Failed`],
+    /// and [`FillError::TooLarge`] if the string did not fit in a buffer of
+    /// `max_capacity` bytes.
+    ///
+    /// [`FillError::Failed`]: enum.FillError.html#variant.Failed
+    /// [`FillStatus::Failed`]: enum.FillStatus.html#variant.Failed
+    /// [`FillError::TooLarge`]: enum.FillError.html#variant.TooLarge
+    ///
+    /// # Safety
+    ///
+    /// If `f` returns [`FillStatus::Done`], it must have initialized the
+    /// buffer up to and including a nul byte, or in its entirety.
+    ///
+    /// [`FillStatus::Done`]: enum.FillStatus.html#variant.Done
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{c_char, CString, FillStatus};
+    ///
+    /// let msg = b"a rather long message\0";
+    /// let s = unsafe {
+    ///     CString::from_ffi_fill_growing(4, 1024, |buf: *mut c_char, len| {
+    ///         if len < msg.len() {
+    ///             return FillStatus::TooSmall::<()>;
+    ///         }
+    ///         std::ptr::copy_nonoverlapping(msg.as_ptr() as *const c_char, buf, msg.len());
+    ///         FillStatus::Done
+    ///     })
+    /// };
+    /// assert_eq!(s.unwrap().as_bytes_with_nul(), msg);
+    /// ```
+    pub unsafe fn from_ffi_fill_growing<F, E>(
+        initial_capacity: usize,
+        max_capacity: usize,
+        mut f: F,
+    ) -> Result<CString, FillError<E>>
+    where
+        F: FnMut(*mut c_char, usize) -> FillStatus<E>,
+    {
+        let mut capacity = initial_capacity.clamp(1, max_capacity.max(1));
+        loop {
+            let mut v = Vec::with_capacity(capacity);
+            match f(v.as_mut_ptr() as *mut c_char, capacity) {
+                FillStatus::Done => {
+                    if let Some(len) = strnlen(v.as_ptr(), capacity) {
+                        v.set_len(len);
+                        return Ok(CString::from_vec_unchecked(v));
+                    }
+                }
+                FillStatus::TooSmall => {}
+                FillStatus::Failed(e) => return Err(FillError::Failed(e)),
+            }
+            if capacity >= max_capacity {
+                return Err(FillError::TooLarge);
+            }
+            capacity = capacity.saturating_mul(2).min(max_capacity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ptr;
+
+    unsafe fn write(buf: *mut c_char, len: usize, s: &[u8]) -> FillStatus<i32> {
+        if len < s.len() {
+            return FillStatus::TooSmall;
+        }
+        ptr::copy_nonoverlapping(s.as_ptr() as *const c_char, buf, s.len());
+        FillStatus::Done
+    }
+
+    #[test]
+    fn fill() {
+        let s = unsafe { CString::from_ffi_fill(16, |buf, _| write_ok(buf, b"abc\0xyz")) };
+        assert_eq!(s.unwrap().as_bytes(), b"abc");
+
+        let s = unsafe { CString::from_ffi_fill(3, |buf, _| write_ok(buf, b"abc")) };
+        assert_eq!(s, Err(FillError::NotNulTerminated));
+
+        let s = unsafe { CString::from_ffi_fill(3, |_, _| Err(22)) };
+        assert_eq!(s, Err(FillError::Failed(22)));
+    }
+
+    unsafe fn write_ok(buf: *mut c_char, s: &[u8]) -> Result<(), i32> {
+        ptr::copy_nonoverlapping(s.as_ptr() as *const c_char, buf, s.len());
+        Ok(())
+    }
+
+    #[test]
+    fn fill_growing() {
+        let mut calls = 0;
+        let s = unsafe {
+            CString::from_ffi_fill_growing(1, 64, |buf, len| {
+                calls += 1;
+                write(buf, len, b"0123456789\0")
+            })
+        };
+        assert_eq!(s.unwrap().as_bytes(), b"0123456789");
+        // 1, 2, 4, 8, 16
+        assert_eq!(calls, 5);
+
+        // A buffer filled without a terminator, as `gethostname` may do on
+        // truncation, is grown as well.
+        let s = unsafe {
+            CString::from_ffi_fill_growing(2, 64, |buf, len| {
+                let _ = write(buf, len, &b"abcdef\0"[..len.min(7)]);
+                FillStatus::Done::<i32>
+            })
+        };
+        assert_eq!(s.unwrap().as_bytes(), b"abcdef");
+
+        let s = unsafe {
+            CString::from_ffi_fill_growing(1, 6, |buf, len| write(buf, len, b"0123456789\0"))
+        };
+        assert_eq!(s, Err(FillError::TooLarge));
+
+        let s = unsafe { CString::from_ffi_fill_growing(1, 6, |_, _| FillStatus::Failed(5)) };
+        assert_eq!(s, Err(FillError::Failed(5)));
+    }
+}
@@ -0,0 +1,266 @@
+use core::borrow::Borrow;
+use core::ptr::NonNull;
+use core::{fmt, mem, ops, slice};
+
+#[cfg(feature = "libc")]
+use libc;
+
+use {c_char, CStr};
+
+/// Releases C strings allocated outside of Rust.
+///
+/// This is implemented for closures and functions taking the pointer to
+/// release, and for [`LibcFree`] if the `libc` feature is enabled.
+///
+/// [`LibcFree`]: struct.LibcFree.html
+pub trait Deallocator {
+    /// Releases the string at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a string allocated in a way this deallocator can
+    /// release, and must not be used afterwards.
+    unsafe fn deallocate(&mut self, ptr: *mut c_char);
+}
+
+impl<F: FnMut(*mut c_char)> Deallocator for F {
+    #[inline]
+    unsafe fn deallocate(&mut self, ptr: *mut c_char) {
+        self(ptr)
+    }
+}
+
+/// Allows passing C functions such as `g_free` directly.
+impl Deallocator for unsafe extern "C" fn(*mut c_char) {
+    #[inline]
+    unsafe fn deallocate(&mut self, ptr: *mut c_char) {
+        self(ptr)
+    }
+}
+
+/// A [`Deallocator`] calling `free` from the C library, for strings returned
+/// by `malloc`, `strdup` and the like.
+///
+/// [`Deallocator`]: trait.Deallocator.html
+#[cfg(feature = "libc")]
+#[derive(Clone, Copy, Default, Debug)]
+pub struct LibcFree;
+
+#[cfg(feature = "libc")]
+impl Deallocator for LibcFree {
+    #[inline]
+    unsafe fn deallocate(&mut self, ptr: *mut c_char) {
+        libc::free(ptr as *mut libc::c_void)
+    }
+}
+
+/// A [`ForeignCString`] allocated by the `malloc` family of functions.
+///
+/// [`ForeignCString`]: struct.ForeignCString.html
+#[cfg(feature = "libc")]
+pub type MallocCString = ForeignCString<LibcFree>;
+
+/// An owned C string allocated by foreign code.
+///
+/// [`CString::from_raw`] may only take back strings created by
+/// [`CString::into_raw`]. C libraries instead hand out strings which must be
+/// released with their own function, such as `free` or `g_free`. A
+/// `ForeignCString` owns such a string, derefs to [`CStr`], and releases it
+/// with its [`Deallocator`] when dropped.
+///
+/// [`CString::from_raw`]: struct.CString.html#method.from_raw
+/// [`CString::into_raw`]: struct.CString.html#method.into_raw
+/// [`CStr`]: struct.CStr.html
+/// [`Deallocator`]: trait.Deallocator.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{c_char, ForeignCString};
+///
+/// // Stands in for a C function returning a string the caller must free.
+/// fn get_name() -> *mut c_char {
+///     Box::into_raw(Box::new(*b"foo\0")) as *mut c_char
+/// }
+///
+/// let name = unsafe {
+///     ForeignCString::from_raw(get_name(), |ptr: *mut c_char| {
+///         drop(Box::from_raw(ptr as *mut [u8; 4]));
+///     })
+/// };
+/// assert_eq!(name.unwrap().to_bytes(), b"foo");
+/// ```
+pub struct ForeignCString<D: Deallocator> {
+    ptr: NonNull<c_char>,
+    /// Length of the string, including its nul terminator.
+    len: usize,
+    deallocator: D,
+}
+
+// A `ForeignCString` owns its string like a `CString` does. Whether it may be
+// released from another thread is up to the deallocator.
+unsafe impl<D: Deallocator + Send> Send for ForeignCString<D> {}
+unsafe impl<D: Deallocator + Sync> Sync for ForeignCString<D> {}
+
+impl<D: Deallocator> ForeignCString<D> {
+    /// Takes ownership of a C string allocated by foreign code.
+    ///
+    /// Returns `None` if `ptr` is null, in which case `deallocator` is
+    /// dropped without being called.
+    ///
+    /// # Safety
+    ///
+    /// If not null, `ptr` must point to a valid nul-terminated string, which
+    /// is not used by anything else while the `ForeignCString` exists and can
+    /// be released by `deallocator`.
+    pub unsafe fn from_raw(ptr: *mut c_char, deallocator: D) -> Option<ForeignCString<D>> {
+        let ptr = NonNull::new(ptr)?;
+        let len = CStr::from_ptr(ptr.as_ptr()).to_bytes_with_nul().len();
+        Some(ForeignCString {
+            ptr,
+            len,
+            deallocator,
+        })
+    }
+
+    /// Extracts a [`CStr`] slice containing the entire string.
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe {
+            let bytes = slice::from_raw_parts(self.ptr.as_ptr() as *const u8, self.len);
+            CStr::from_bytes_with_nul_unchecked(bytes)
+        }
+    }
+
+    /// Returns a reference to the deallocator.
+    #[inline]
+    pub fn deallocator(&self) -> &D {
+        &self.deallocator
+    }
+
+    /// Releases ownership of the string, returning it along with its
+    /// deallocator.
+    #[inline]
+    pub fn into_raw(self) -> (*mut c_char, D) {
+        let this = mem::ManuallyDrop::new(self);
+        let deallocator = unsafe { (&this.deallocator as *const D).read() };
+        (this.ptr.as_ptr(), deallocator)
+    }
+}
+
+#[cfg(feature = "libc")]
+impl ForeignCString<LibcFree> {
+    /// Takes ownership of a C string allocated by `malloc`.
+    ///
+    /// # Safety
+    ///
+    /// See [`from_raw`](#method.from_raw).
+    #[inline]
+    pub unsafe fn from_malloc(ptr: *mut c_char) -> Option<MallocCString> {
+        ForeignCString::from_raw(ptr, LibcFree)
+    }
+}
+
+impl<D: Deallocator> Drop for ForeignCString<D> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.deallocator.deallocate(self.ptr.as_ptr()) }
+    }
+}
+
+impl<D: Deallocator> ops::Deref for ForeignCString<D> {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl<D: Deallocator> AsRef<CStr> for ForeignCString<D> {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl<D: Deallocator> Borrow<CStr> for ForeignCString<D> {
+    #[inline]
+    fn borrow(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl<D: Deallocator> fmt::Debug for ForeignCString<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl<D: Deallocator, E: Deallocator> PartialEq<ForeignCString<E>> for ForeignCString<D> {
+    #[inline]
+    fn eq(&self, other: &ForeignCString<E>) -> bool {
+        self.as_c_str() == other.as_c_str()
+    }
+}
+
+impl<D: Deallocator> Eq for ForeignCString<D> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::ptr;
+    use std::boxed::Box;
+
+    fn leak(bytes: &[u8]) -> *mut c_char {
+        Box::into_raw(Box::<[u8]>::from(bytes)) as *mut c_char
+    }
+
+    #[test]
+    fn closure() {
+        let freed = Cell::new(ptr::null_mut());
+        let raw = leak(b"abc\0");
+        let s = unsafe { ForeignCString::from_raw(raw, |p| freed.set(p)) }.unwrap();
+        assert_eq!(s.to_bytes_with_nul(), b"abc\0");
+        assert_eq!(s.as_ptr(), raw as *const c_char);
+        drop(s);
+        assert_eq!(freed.get(), raw);
+        unsafe {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                raw as *mut u8,
+                4,
+            )))
+        };
+
+        let s = unsafe { ForeignCString::from_raw(ptr::null_mut(), |_| panic!()) };
+        assert!(s.is_none());
+    }
+
+    #[test]
+    fn fn_pointer() {
+        fn release(p: *mut c_char) {
+            unsafe {
+                drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                    p as *mut u8,
+                    3,
+                )))
+            }
+        }
+        let a = unsafe { ForeignCString::from_raw(leak(b"xy\0"), release as fn(_)) }.unwrap();
+        let b = unsafe { ForeignCString::from_raw(leak(b"xy\0"), release) }.unwrap();
+        assert_eq!(a, b);
+        let (raw, dealloc) = a.into_raw();
+        dealloc(raw);
+    }
+
+    #[cfg(feature = "libc")]
+    #[test]
+    fn malloc() {
+        let raw = unsafe { libc::strdup(b"dup\0".as_ptr() as *const c_char) };
+        let s = unsafe { MallocCString::from_malloc(raw) }.unwrap();
+        assert_eq!(s.to_bytes(), b"dup");
+    }
+}
@@ -0,0 +1,451 @@
+use alloc::vec::Vec;
+use core::{cmp, fmt};
+#[cfg(feature = "std")]
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+
+use memchr::memchr;
+
+use {CStr, NulError};
+
+/// Size of the arena chunks holding the interned strings.
+const CHUNK_SIZE: usize = 4096;
+
+/// A compact identifier for a string stored in a [`CStrInterner`].
+///
+/// A symbol is only meaningful for the interner which returned it.
+///
+/// [`CStrInterner`]: struct.CStrInterner.html
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Returns the numeric value of the symbol.
+    ///
+    /// Symbols are numbered consecutively from 0 in the order the strings
+    /// were interned.
+    #[inline]
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// 64-bit FNV-1a hash, which is fast for the short strings usually interned.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// A string interner handing out [`Symbol`]s for C strings.
+///
+/// Each distinct string is stored once, with its nul terminator, in large
+/// arena chunks which are never reallocated: references returned by
+/// [`resolve`] stay valid for as long as the interner, and can be passed to C
+/// as is. Resolving a symbol is a simple index, and looking up a string is a
+/// hash table probe.
+///
+/// See [`SyncCStrInterner`] for a variant which can be shared between
+/// threads.
+///
+/// [`Symbol`]: struct.Symbol.html
+/// [`resolve`]: #method.resolve
+/// [`SyncCStrInterner`]: struct.SyncCStrInterner.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{CStr, CStrInterner};
+///
+/// let mut interner = CStrInterner::new();
+/// let a = interner.intern_str("malloc").unwrap();
+/// let b = interner.intern(CStr::from_bytes_with_nul(b"malloc\0").unwrap());
+/// assert_eq!(a, b);
+/// assert_eq!(interner.resolve(a).to_bytes(), b"malloc");
+/// assert_eq!(interner.get_str("malloc"), Some(a));
+/// assert_eq!(interner.get_str("free"), None);
+/// ```
+#[derive(Default)]
+pub struct CStrInterner {
+    chunks: Vec<Vec<u8>>,
+    strings: Vec<*const CStr>,
+    /// Open addressing hash table with linear probing, holding symbol values
+    /// plus one so that 0 marks an empty slot. Its length is zero or a power
+    /// of two, and it is kept at most half full.
+    table: Vec<u32>,
+}
+
+// The raw pointers only point into the chunks owned by the interner.
+unsafe impl Send for CStrInterner {}
+unsafe impl Sync for CStrInterner {}
+
+impl CStrInterner {
+    /// Creates an empty interner.
+    #[inline]
+    pub fn new() -> CStrInterner {
+        CStrInterner::default()
+    }
+
+    /// Returns the number of distinct strings in the interner.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns whether the interner is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns the string a symbol stands for.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `symbol` was not returned by this interner.
+    #[inline]
+    pub fn resolve(&self, symbol: Symbol) -> &CStr {
+        unsafe { &*self.strings[symbol.0 as usize] }
+    }
+
+    /// Returns the symbol of a string, if it was interned.
+    #[inline]
+    pub fn get(&self, s: &CStr) -> Option<Symbol> {
+        self.get_bytes(s.to_bytes())
+    }
+
+    /// Returns the symbol of a string, if it was interned.
+    #[inline]
+    pub fn get_str(&self, s: &str) -> Option<Symbol> {
+        self.get_bytes(s.as_bytes())
+    }
+
+    fn get_bytes(&self, bytes: &[u8]) -> Option<Symbol> {
+        if self.table.is_empty() {
+            return None;
+        }
+        self.probe(bytes, fnv1a(bytes)).1
+    }
+
+    /// Interns a string and returns its symbol.
+    ///
+    /// The string is only copied if it was not interned yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interner already holds `u32::MAX` strings.
+    #[inline]
+    pub fn intern(&mut self, s: &CStr) -> Symbol {
+        self.intern_bytes(s.to_bytes())
+    }
+
+    /// Interns a Rust string and returns its symbol.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NulError`] if the string contains a nul byte.
+    ///
+    /// [`NulError`]: struct.NulError.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interner already holds `u32::MAX` strings.
+    pub fn intern_str(&mut self, s: &str) -> Result<Symbol, NulError> {
+        let bytes = s.as_bytes();
+        match memchr(0, bytes) {
+            Some(pos) => Err(NulError(pos, bytes.to_vec())),
+            None => Ok(self.intern_bytes(bytes)),
+        }
+    }
+
+    /// Interns `bytes`, which must not contain a nul byte.
+    fn intern_bytes(&mut self, bytes: &[u8]) -> Symbol {
+        let hash = fnv1a(bytes);
+        let mut slot = None;
+        if !self.table.is_empty() {
+            let (s, found) = self.probe(bytes, hash);
+            if let Some(symbol) = found {
+                return symbol;
+            }
+            slot = Some(s);
+        }
+        // Only grow on insertion, so that interning a known string never
+        // rehashes the table.
+        if (self.strings.len() + 1) * 2 > self.table.len() {
+            self.grow();
+            slot = None;
+        }
+        let slot = match slot {
+            Some(slot) => slot,
+            None => self.probe(bytes, hash).0,
+        };
+
+        let index = self.strings.len();
+        assert!(index < u32::MAX as usize, "too many interned strings");
+        let s = self.alloc(bytes);
+        self.strings.push(s);
+        self.table[slot] = index as u32 + 1;
+        Symbol(index as u32)
+    }
+
+    /// Returns the slot where `bytes` is or would be stored, and its symbol
+    /// if it is already interned. The table must not be empty.
+    fn probe(&self, bytes: &[u8], hash: u64) -> (usize, Option<Symbol>) {
+        let mask = self.table.len() - 1;
+        let mut slot = hash as usize & mask;
+        loop {
+            match self.table[slot] {
+                0 => return (slot, None),
+                n => {
+                    let symbol = Symbol(n - 1);
+                    if self.resolve(symbol).to_bytes() == bytes {
+                        return (slot, Some(symbol));
+                    }
+                }
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    fn grow(&mut self) {
+        let len = cmp::max(16, self.table.len() * 2);
+        let mut table = ::alloc::vec![0; len];
+        for (index, &s) in self.strings.iter().enumerate() {
+            let bytes = unsafe { (*s).to_bytes() };
+            let mut slot = fnv1a(bytes) as usize & (len - 1);
+            while table[slot] != 0 {
+                slot = (slot + 1) & (len - 1);
+            }
+            table[slot] = index as u32 + 1;
+        }
+        self.table = table;
+    }
+
+    /// Copies `bytes` and a nul terminator into the arena.
+    fn alloc(&mut self, bytes: &[u8]) -> *const CStr {
+        let len = bytes.len() + 1;
+        let fits = match self.chunks.last() {
+            Some(chunk) => chunk.capacity() - chunk.len() >= len,
+            None => false,
+        };
+        if !fits {
+            self.chunks
+                .push(Vec::with_capacity(cmp::max(CHUNK_SIZE, len)));
+        }
+        // Chunks are never pushed to beyond their capacity, so the strings
+        // they hold never move.
+        let chunk = self.chunks.last_mut().unwrap();
+        let start = chunk.len();
+        chunk.extend_from_slice(bytes);
+        chunk.push(0);
+        unsafe { CStr::from_bytes_with_nul_unchecked(&chunk[start..]) }
+    }
+}
+
+impl fmt::Debug for CStrInterner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries((0..self.strings.len() as u32).map(|i| (Symbol(i), self.resolve(Symbol(i)))))
+            .finish()
+    }
+}
+
+/// A [`CStrInterner`] which can be shared between threads.
+///
+/// Lookups take a read lock, and only interning a new string takes a write
+/// lock. As strings are never moved, resolved strings stay borrowed from the
+/// interner rather than from a lock guard.
+///
+/// [`CStrInterner`]: struct.CStrInterner.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::SyncCStrInterner;
+///
+/// let interner = SyncCStrInterner::new();
+/// let symbols: Vec<_> = std::thread::scope(|scope| {
+///     let handles: Vec<_> = (0..4)
+///         .map(|_| scope.spawn(|| interner.intern_str("shared").unwrap()))
+///         .collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).collect()
+/// });
+/// assert!(symbols.iter().all(|&s| s == symbols[0]));
+/// assert_eq!(interner.len(), 1);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Default, Debug)]
+pub struct SyncCStrInterner {
+    inner: RwLock<CStrInterner>,
+}
+
+#[cfg(feature = "std")]
+impl SyncCStrInterner {
+    /// Creates an empty interner.
+    #[inline]
+    pub fn new() -> SyncCStrInterner {
+        SyncCStrInterner::default()
+    }
+
+    // Interning never leaves the interner in an inconsistent state, so a
+    // poisoned lock can safely be used.
+    fn read(&self) -> RwLockReadGuard<'_, CStrInterner> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of distinct strings in the interner.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns whether the interner is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Returns the string a symbol stands for.
+    ///
+    /// See [`CStrInterner::resolve`](struct.CStrInterner.html#method.resolve).
+    #[inline]
+    pub fn resolve(&self, symbol: Symbol) -> &CStr {
+        let s: *const CStr = self.read().resolve(symbol);
+        // The string lives as long as the interner, not only as the guard.
+        unsafe { &*s }
+    }
+
+    /// Returns the symbol of a string, if it was interned.
+    #[inline]
+    pub fn get(&self, s: &CStr) -> Option<Symbol> {
+        self.read().get(s)
+    }
+
+    /// Returns the symbol of a string, if it was interned.
+    #[inline]
+    pub fn get_str(&self, s: &str) -> Option<Symbol> {
+        self.read().get_str(s)
+    }
+
+    /// Interns a string and returns its symbol.
+    ///
+    /// See [`CStrInterner::intern`](struct.CStrInterner.html#method.intern).
+    pub fn intern(&self, s: &CStr) -> Symbol {
+        match self.get(s) {
+            Some(symbol) => symbol,
+            None => self
+                .inner
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .intern(s),
+        }
+    }
+
+    /// Interns a Rust string and returns its symbol.
+    ///
+    /// See [`CStrInterner::intern_str`](struct.CStrInterner.html#method.intern_str).
+    pub fn intern_str(&self, s: &str) -> Result<Symbol, NulError> {
+        match self.get_str(s) {
+            Some(symbol) => Ok(symbol),
+            None => self
+                .inner
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .intern_str(s),
+        }
+    }
+
+    /// Returns the underlying interner.
+    #[inline]
+    pub fn into_inner(self) -> CStrInterner {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<CStrInterner> for SyncCStrInterner {
+    #[inline]
+    fn from(interner: CStrInterner) -> SyncCStrInterner {
+        SyncCStrInterner {
+            inner: RwLock::new(interner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::{String, ToString};
+    use std::vec::Vec;
+
+    #[test]
+    fn intern() {
+        let mut interner = CStrInterner::new();
+        assert_eq!(interner.get_str(""), None);
+
+        let a = interner.intern_str("a").unwrap();
+        let empty = interner.intern_str("").unwrap();
+        assert_eq!((a.as_u32(), empty.as_u32()), (0, 1));
+        assert_eq!(interner.intern_str("a"), Ok(a));
+        assert_eq!(
+            interner.get(CStr::from_bytes_with_nul(b"\0").unwrap()),
+            Some(empty)
+        );
+        assert_eq!(interner.resolve(a).to_bytes_with_nul(), b"a\0");
+        assert_eq!(interner.len(), 2);
+
+        let err = interner.intern_str("a\0b").unwrap_err();
+        assert_eq!(err.nul_position(), 1);
+        assert_eq!(interner.get_str("a\0b"), None);
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn many() {
+        let mut interner = CStrInterner::new();
+        let names: Vec<String> = (0..5000).map(|i| i.to_string()).collect();
+        let long: String = "x".repeat(CHUNK_SIZE * 2);
+
+        let symbols: Vec<_> = names
+            .iter()
+            .chain(Some(&long))
+            .map(|s| interner.intern_str(s).unwrap())
+            .collect();
+        let first = interner.resolve(symbols[0]).as_ptr();
+
+        for (s, &symbol) in names.iter().chain(Some(&long)).zip(&symbols) {
+            assert_eq!(interner.intern_str(s), Ok(symbol));
+            assert_eq!(interner.get_str(s), Some(symbol));
+            assert_eq!(interner.resolve(symbol).to_bytes(), s.as_bytes());
+        }
+        assert_eq!(interner.resolve(symbols[0]).as_ptr(), first);
+        assert_eq!(interner.len(), 5001);
+    }
+
+    #[test]
+    fn reintern_does_not_grow() {
+        let mut interner = CStrInterner::new();
+        for i in 0..8 {
+            interner.intern_str(&i.to_string()).unwrap();
+        }
+        let table_len = interner.table.len();
+        assert_eq!(interner.intern_str("0").unwrap().as_u32(), 0);
+        assert_eq!(interner.table.len(), table_len);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sync() {
+        let interner = SyncCStrInterner::new();
+        let a = interner.intern_str("a").unwrap();
+        let s = interner.resolve(a);
+        assert_eq!(interner.intern(s), a);
+        assert_eq!(interner.get_str("b"), None);
+        let interner = interner.into_inner();
+        assert_eq!(interner.get_str("a"), Some(a));
+    }
+}
@@ -0,0 +1,206 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use memchr::memchr;
+
+use {CStr, CString, NulError};
+
+/// Conversion into a C string, borrowing whenever possible.
+///
+/// This trait is meant to be used as a bound on the arguments of safe FFI
+/// wrappers, so that callers can pass whichever string type they have at hand.
+/// Inputs which already end with a nul terminator (including `&str` literals
+/// such as `"foo\0"`) are borrowed as-is, and an allocation only happens when a
+/// terminator needs to be appended.
+///
+/// # Errors
+///
+/// Returns a [`NulError`] if the input contains a nul byte anywhere but in its
+/// last position.
+///
+/// [`NulError`]: struct.NulError.html
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+/// use cstr_core::{CStr, IntoCStr};
+///
+/// fn strlen<'a, S: IntoCStr<'a>>(s: S) -> usize {
+///     let s = s.into_c_str().unwrap();
+///     s.to_bytes().len()
+/// }
+///
+/// assert_eq!(strlen("foo"), 3);
+/// assert_eq!(strlen(&b"foo\0"[..]), 3);
+/// assert_eq!(strlen(String::from("foo")), 3);
+/// assert_eq!(strlen(CStr::from_bytes_with_nul(b"foo\0").unwrap()), 3);
+///
+/// assert!(matches!("foo\0".into_c_str(), Ok(Cow::Borrowed(_))));
+/// assert!(matches!("foo".into_c_str(), Ok(Cow::Owned(_))));
+/// assert!("f\0oo".into_c_str().is_err());
+/// ```
+pub trait IntoCStr<'a> {
+    /// Converts `self` into a C string.
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError>;
+}
+
+/// Borrows `bytes` if they are already nul-terminated, and copies them with an
+/// added terminator otherwise.
+fn from_slice(bytes: &[u8]) -> Result<Cow<'_, CStr>, NulError> {
+    match memchr(0, bytes) {
+        Some(i) if i + 1 == bytes.len() => {
+            Ok(Cow::Borrowed(unsafe { CStr::from_bytes_with_nul_unchecked(bytes) }))
+        }
+        Some(i) => Err(NulError(i, bytes.to_vec())),
+        None => {
+            let mut v = Vec::with_capacity(bytes.len() + 1);
+            v.extend_from_slice(bytes);
+            Ok(Cow::Owned(unsafe { CString::from_vec_unchecked(v) }))
+        }
+    }
+}
+
+/// Reuses the buffer of `bytes`, only appending a terminator if needed.
+fn from_vec<'a>(mut bytes: Vec<u8>) -> Result<Cow<'a, CStr>, NulError> {
+    match memchr(0, &bytes) {
+        Some(i) if i + 1 == bytes.len() => {
+            bytes.pop();
+            Ok(Cow::Owned(unsafe { CString::from_vec_unchecked(bytes) }))
+        }
+        Some(i) => Err(NulError(i, bytes)),
+        None => Ok(Cow::Owned(unsafe { CString::from_vec_unchecked(bytes) })),
+    }
+}
+
+impl<'a> IntoCStr<'a> for &'a CStr {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl<'a> IntoCStr<'a> for &'a CString {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl<'a> IntoCStr<'a> for CString {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        Ok(Cow::Owned(self))
+    }
+}
+
+impl<'a> IntoCStr<'a> for Cow<'a, CStr> {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        Ok(self)
+    }
+}
+
+impl<'a> IntoCStr<'a> for &'a [u8] {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        from_slice(self)
+    }
+}
+
+impl<'a, const N: usize> IntoCStr<'a> for &'a [u8; N] {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        from_slice(self)
+    }
+}
+
+impl<'a> IntoCStr<'a> for &'a Vec<u8> {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        from_slice(self)
+    }
+}
+
+impl<'a> IntoCStr<'a> for Vec<u8> {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        from_vec(self)
+    }
+}
+
+impl<'a> IntoCStr<'a> for &'a str {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        from_slice(self.as_bytes())
+    }
+}
+
+impl<'a> IntoCStr<'a> for &'a String {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        from_slice(self.as_bytes())
+    }
+}
+
+impl<'a> IntoCStr<'a> for String {
+    #[inline]
+    fn into_c_str(self) -> Result<Cow<'a, CStr>, NulError> {
+        from_vec(self.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_borrowed<'a, S: IntoCStr<'a>>(s: S) -> bool {
+        match s.into_c_str().unwrap() {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }
+    }
+
+    #[test]
+    fn borrows_when_terminated() {
+        assert!(is_borrowed("foo\0"));
+        assert!(is_borrowed(&b"foo\0"[..]));
+        assert!(is_borrowed(b"foo\0"));
+        assert!(!is_borrowed("foo"));
+        assert!(!is_borrowed(b"foo".to_vec()));
+
+        let s = String::from("foo\0");
+        assert!(is_borrowed(&s));
+        assert!(!is_borrowed(s));
+        let s = CString::new("foo").unwrap();
+        assert!(is_borrowed(&s));
+        assert!(!is_borrowed(s));
+    }
+
+    #[test]
+    fn owned_reuses_buffer() {
+        let mut s = String::with_capacity(4);
+        s.push_str("foo");
+        let ptr = s.as_ptr();
+        let c = s.into_c_str().unwrap();
+        assert_eq!(c.to_bytes_with_nul(), b"foo\0");
+        assert_eq!(c.as_ptr() as *const u8, ptr);
+
+        let v = b"bar\0".to_vec();
+        let ptr = v.as_ptr();
+        let c = v.into_c_str().unwrap();
+        assert_eq!(c.to_bytes_with_nul(), b"bar\0");
+        assert_eq!(c.as_ptr() as *const u8, ptr);
+    }
+
+    #[test]
+    fn interior_nul() {
+        let err = "f\0oo\0".into_c_str().unwrap_err();
+        assert_eq!(err.nul_position(), 1);
+        assert_eq!(err.into_vec(), b"f\0oo\0");
+        let err = String::from("\0\0").into_c_str().unwrap_err();
+        assert_eq!(err.nul_position(), 0);
+        assert_eq!("".into_c_str().unwrap().to_bytes(), b"");
+    }
+}
@@ -0,0 +1,117 @@
+use core::iter::FusedIterator;
+
+use memchr::memchr;
+
+use CStr;
+
+/// An iterator over the nul-terminated strings packed in a byte slice.
+///
+/// This `struct` is created by [`CStr::iter_from_bytes`]. See its
+/// documentation for more.
+///
+/// [`CStr::iter_from_bytes`]: struct.CStr.html#method.iter_from_bytes
+#[derive(Clone, Debug)]
+pub struct CStrs<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> CStrs<'a> {
+    /// Returns the bytes which have not been consumed by the iterator yet.
+    ///
+    /// Once the iterator is exhausted, this is the trailing fragment of the
+    /// slice which was not nul-terminated, which is empty if the slice ended
+    /// with a nul byte.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::CStr;
+    ///
+    /// let mut iter = CStr::iter_from_bytes(b"a\0b\0trunc");
+    /// assert_eq!(iter.by_ref().count(), 2);
+    /// assert_eq!(iter.remainder(), b"trunc");
+    /// ```
+    #[inline]
+    pub fn remainder(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<'a> Iterator for CStrs<'a> {
+    type Item = &'a CStr;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a CStr> {
+        let nul = memchr(0, self.bytes)?;
+        let (s, rest) = self.bytes.split_at(nul + 1);
+        self.bytes = rest;
+        Some(unsafe { CStr::from_bytes_with_nul_unchecked(s) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.bytes.len()))
+    }
+}
+
+impl<'a> FusedIterator for CStrs<'a> {}
+
+impl CStr {
+    /// Returns an iterator over a sequence of nul-terminated strings stored
+    /// back to back in a byte slice.
+    ///
+    /// This is the format of `/proc/<pid>/cmdline` and `/proc/<pid>/environ`,
+    /// of the output of `find -print0`, and of ELF string sections. Each
+    /// nul byte ends a string, so consecutive nul bytes yield empty strings.
+    ///
+    /// Bytes following the last nul byte are not yielded; they can be
+    /// retrieved with [`CStrs::remainder`] once the iterator is exhausted.
+    ///
+    /// [`CStrs::remainder`]: struct.CStrs.html#method.remainder
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::CStr;
+    ///
+    /// let cmdline = b"ls\0-l\0/tmp\0";
+    /// let args: Vec<_> = CStr::iter_from_bytes(cmdline).map(CStr::to_bytes).collect();
+    /// assert_eq!(args, [&b"ls"[..], b"-l", b"/tmp"]);
+    /// ```
+    #[inline]
+    pub fn iter_from_bytes(bytes: &[u8]) -> CStrs<'_> {
+        CStrs { bytes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn collect(bytes: &[u8]) -> (Vec<&[u8]>, &[u8]) {
+        let mut iter = CStr::iter_from_bytes(bytes);
+        let strings = iter.by_ref().map(CStr::to_bytes).collect();
+        (strings, iter.remainder())
+    }
+
+    #[test]
+    fn iter() {
+        assert_eq!(collect(b""), (Vec::<&[u8]>::new(), &b""[..]));
+        assert_eq!(collect(b"abc"), (Vec::<&[u8]>::new(), &b"abc"[..]));
+        assert_eq!(collect(b"\0"), (vec![&b""[..]], &b""[..]));
+        assert_eq!(
+            collect(b"a\0\0bc\0d"),
+            (vec![&b"a"[..], b"", b"bc"], &b"d"[..])
+        );
+    }
+
+    #[test]
+    fn fused() {
+        let mut iter = CStr::iter_from_bytes(b"a\0b");
+        assert_eq!(iter.next().map(CStr::to_bytes), Some(&b"a"[..]));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.remainder(), b"b");
+    }
+}
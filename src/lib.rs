@@ -11,14 +11,28 @@
 #![no_std]
 #![cfg_attr(feature = "alloc", feature(alloc))]
 
-#[cfg(test)]
-#[macro_use]
+#[cfg(any(test, feature = "std"))]
+#[cfg_attr(test, macro_use)]
 extern crate std;
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "allocator-api2")]
+extern crate allocator_api2;
+#[cfg(feature = "bumpalo")]
+extern crate bumpalo;
 extern crate cty;
+#[cfg(feature = "libc")]
+extern crate libc;
 extern crate memchr;
+#[cfg(feature = "serde")]
+extern crate serde as serde_crate;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
+#[cfg(feature = "subtle")]
+extern crate subtle;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
 
 #[cfg(feature = "alloc")]
 use alloc::sync::Arc;
@@ -36,6 +50,7 @@ use alloc::vec::Vec;
 use core::{mem, ops, ptr};
 
 use core::cmp::Ordering;
+use core::error::Error;
 use core::fmt::{self, Write};
 use core::slice;
 use core::str::{self, Utf8Error};
@@ -43,6 +58,88 @@ use core::str::{self, Utf8Error};
 /// Re-export c_char
 pub use cty::c_char;
 
+#[cfg(feature = "alloc")]
+pub use arc_cstring::{ArcCString, WeakCString};
+pub use array::ArrayCString;
+pub use ascii_c_str::{AsciiCStr, AsciiError};
+#[cfg(feature = "alloc")]
+pub use ascii_c_str::AsciiCString;
+#[cfg(feature = "bumpalo")]
+#[doc(hidden)]
+pub use bump::format_in as __format_in;
+#[cfg(feature = "bumpalo")]
+pub use bump::alloc_cstr_from_str;
+pub use c_utf8::CUtf8Str;
+#[cfg(feature = "alloc")]
+pub use c_utf8::CUtf8String;
+pub use copy::{BufferTooSmallError, CopyResult};
+#[cfg(feature = "allocator-api2")]
+pub use cstring_in::{CStringIn, NulErrorIn};
+#[cfg(feature = "alloc")]
+pub use cstring_vec::{CStrPtrArray, CStringVec, CStringVecIter};
+pub use field::CStrField;
+pub use fill::{FillError, FillStatus};
+pub use foreign::{Deallocator, ForeignCString};
+#[cfg(feature = "libc")]
+pub use foreign::{LibcFree, MallocCString};
+#[cfg(feature = "alloc")]
+pub use interner::{CStrInterner, Symbol};
+#[cfg(feature = "std")]
+pub use interner::SyncCStrInterner;
+#[cfg(feature = "alloc")]
+pub use into_c_str::IntoCStr;
+pub use iter::CStrs;
+pub use natural::NaturalOrder;
+#[cfg(feature = "alloc")]
+pub use secret::{SecretCString, SecretNulError};
+#[cfg(feature = "alloc")]
+pub use small_cstring::SmallCString;
+pub use strtab::{StrTab, StrTabError, StrTabIter};
+#[cfg(feature = "alloc")]
+pub use strtab::{FinishedStrTab, StrTabBuilder};
+#[cfg(feature = "alloc")]
+pub use try_alloc::{AllocError, TryNewError};
+#[cfg(feature = "alloc")]
+pub use with_cstr::with_cstr;
+pub use with_cstr::{with_cstr_on_stack, WithCStrError, MAX_STACK_ALLOCATION};
+
+#[cfg(feature = "alloc")]
+mod arc_cstring;
+mod array;
+mod ascii_c_str;
+#[cfg(feature = "bumpalo")]
+#[macro_use]
+mod bump;
+mod c_utf8;
+mod copy;
+mod core_ffi;
+mod ct_eq;
+#[cfg(feature = "allocator-api2")]
+mod cstring_in;
+#[cfg(feature = "alloc")]
+mod cstring_vec;
+mod field;
+mod fill;
+mod foreign;
+#[cfg(feature = "alloc")]
+mod interner;
+#[cfg(feature = "alloc")]
+mod into_c_str;
+mod iter;
+mod natural;
+#[cfg(feature = "alloc")]
+mod secret;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "alloc")]
+mod small_cstring;
+#[cfg(all(feature = "std", unix))]
+mod std_impls;
+mod strtab;
+#[cfg(feature = "alloc")]
+mod try_alloc;
+mod with_cstr;
+
 #[inline]
 unsafe fn strlen(p: *const c_char) -> usize {
     let mut n = 0;
@@ -322,9 +419,15 @@ pub struct FromBytesWithNulError {
     kind: FromBytesWithNulErrorKind,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
-enum FromBytesWithNulErrorKind {
+/// The reason a [`FromBytesWithNulError`] was returned.
+///
+/// [`FromBytesWithNulError`]: struct.FromBytesWithNulError.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FromBytesWithNulErrorKind {
+    /// The slice contained a nul byte before its last byte, at the given
+    /// position.
     InteriorNul(usize),
+    /// The slice did not contain any nul byte.
     NotNulTerminated,
 }
 
@@ -339,6 +442,43 @@ impl FromBytesWithNulError {
             kind: FromBytesWithNulErrorKind::NotNulTerminated,
         }
     }
+
+    /// Returns the reason the conversion failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{CStr, FromBytesWithNulErrorKind};
+    ///
+    /// let err = CStr::from_bytes_with_nul(b"foo").unwrap_err();
+    /// assert_eq!(err.kind(), FromBytesWithNulErrorKind::NotNulTerminated);
+    /// ```
+    #[inline]
+    pub fn kind(&self) -> FromBytesWithNulErrorKind {
+        self.kind
+    }
+
+    /// Returns the position of the interior nul byte, if that is what caused
+    /// the conversion to fail.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::CStr;
+    ///
+    /// let err = CStr::from_bytes_with_nul(b"f\0oo\0").unwrap_err();
+    /// assert_eq!(err.nul_position(), Some(1));
+    ///
+    /// let err = CStr::from_bytes_with_nul(b"foo").unwrap_err();
+    /// assert_eq!(err.nul_position(), None);
+    /// ```
+    #[inline]
+    pub fn nul_position(&self) -> Option<usize> {
+        match self.kind {
+            FromBytesWithNulErrorKind::InteriorNul(pos) => Some(pos),
+            FromBytesWithNulErrorKind::NotNulTerminated => None,
+        }
+    }
 }
 
 /// An error returned from [`CString::into_string`] to indicate that a UTF-8 error
@@ -601,6 +741,8 @@ impl CString {
 
     /// Converts this `CString` into a boxed [`CStr`].
     ///
+    /// This never allocates, as the string is already stored in a box.
+    ///
     /// [`CStr`]: struct.CStr.html
     ///
     /// # Examples
@@ -856,6 +998,193 @@ impl fmt::Display for IntoStringError {
     }
 }
 
+#[cfg(feature = "alloc")]
+impl Error for NulError {}
+
+impl Error for FromBytesWithNulError {}
+
+#[cfg(feature = "alloc")]
+impl Error for IntoStringError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error covering every way the fallible conversions of this crate can
+/// fail.
+///
+/// Each of the more specific error types converts into a `CStrError`, so code
+/// mixing several constructors can use `?` with a single error type.
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{CStr, CStrError};
+///
+/// fn parse(bytes: &[u8]) -> Result<&str, CStrError> {
+///     Ok(CStr::from_bytes_with_nul(bytes)?.to_str()?)
+/// }
+///
+/// assert_eq!(parse(b"foo\0"), Ok("foo"));
+/// assert!(matches!(parse(b"foo"), Err(CStrError::FromBytesWithNul(_))));
+/// assert!(matches!(parse(b"\xff\0"), Err(CStrError::Utf8(_))));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum CStrError {
+    /// See [`NulError`](struct.NulError.html).
+    #[cfg(feature = "alloc")]
+    Nul(NulError),
+    /// See [`FromBytesWithNulError`](struct.FromBytesWithNulError.html).
+    FromBytesWithNul(FromBytesWithNulError),
+    /// See [`IntoStringError`](struct.IntoStringError.html).
+    #[cfg(feature = "alloc")]
+    IntoString(IntoStringError),
+    /// The string was not valid UTF-8.
+    Utf8(Utf8Error),
+    /// See [`WithCStrError`](enum.WithCStrError.html).
+    WithCStr(WithCStrError),
+    /// See [`BufferTooSmallError`](struct.BufferTooSmallError.html).
+    BufferTooSmall(BufferTooSmallError),
+    /// See [`StrTabError`](enum.StrTabError.html).
+    StrTab(StrTabError),
+    /// See [`AsciiError`](struct.AsciiError.html).
+    Ascii(AsciiError),
+    /// See [`AllocError`](struct.AllocError.html).
+    #[cfg(feature = "alloc")]
+    Alloc(AllocError),
+    /// See [`SecretNulError`](struct.SecretNulError.html).
+    #[cfg(feature = "alloc")]
+    SecretNul(SecretNulError),
+}
+
+#[cfg(feature = "alloc")]
+impl From<NulError> for CStrError {
+    #[inline]
+    fn from(e: NulError) -> CStrError {
+        CStrError::Nul(e)
+    }
+}
+
+impl From<FromBytesWithNulError> for CStrError {
+    #[inline]
+    fn from(e: FromBytesWithNulError) -> CStrError {
+        CStrError::FromBytesWithNul(e)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<IntoStringError> for CStrError {
+    #[inline]
+    fn from(e: IntoStringError) -> CStrError {
+        CStrError::IntoString(e)
+    }
+}
+
+impl From<Utf8Error> for CStrError {
+    #[inline]
+    fn from(e: Utf8Error) -> CStrError {
+        CStrError::Utf8(e)
+    }
+}
+
+impl From<WithCStrError> for CStrError {
+    #[inline]
+    fn from(e: WithCStrError) -> CStrError {
+        CStrError::WithCStr(e)
+    }
+}
+
+impl From<BufferTooSmallError> for CStrError {
+    #[inline]
+    fn from(e: BufferTooSmallError) -> CStrError {
+        CStrError::BufferTooSmall(e)
+    }
+}
+
+impl From<StrTabError> for CStrError {
+    #[inline]
+    fn from(e: StrTabError) -> CStrError {
+        CStrError::StrTab(e)
+    }
+}
+
+impl From<AsciiError> for CStrError {
+    #[inline]
+    fn from(e: AsciiError) -> CStrError {
+        CStrError::Ascii(e)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<AllocError> for CStrError {
+    #[inline]
+    fn from(e: AllocError) -> CStrError {
+        CStrError::Alloc(e)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<SecretNulError> for CStrError {
+    #[inline]
+    fn from(e: SecretNulError) -> CStrError {
+        CStrError::SecretNul(e)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<TryNewError> for CStrError {
+    #[inline]
+    fn from(e: TryNewError) -> CStrError {
+        match e {
+            TryNewError::Nul(e) => CStrError::Nul(e),
+            TryNewError::Alloc(e) => CStrError::Alloc(e),
+        }
+    }
+}
+
+impl fmt::Display for CStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "alloc")]
+            CStrError::Nul(ref e) => e.fmt(f),
+            CStrError::FromBytesWithNul(ref e) => e.fmt(f),
+            #[cfg(feature = "alloc")]
+            CStrError::IntoString(ref e) => e.fmt(f),
+            CStrError::Utf8(ref e) => e.fmt(f),
+            CStrError::WithCStr(ref e) => e.fmt(f),
+            CStrError::BufferTooSmall(ref e) => e.fmt(f),
+            CStrError::StrTab(ref e) => e.fmt(f),
+            CStrError::Ascii(ref e) => e.fmt(f),
+            #[cfg(feature = "alloc")]
+            CStrError::Alloc(ref e) => e.fmt(f),
+            #[cfg(feature = "alloc")]
+            CStrError::SecretNul(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for CStrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            #[cfg(feature = "alloc")]
+            CStrError::Nul(ref e) => e.source(),
+            CStrError::FromBytesWithNul(ref e) => e.source(),
+            #[cfg(feature = "alloc")]
+            CStrError::IntoString(ref e) => e.source(),
+            CStrError::Utf8(ref e) => e.source(),
+            CStrError::WithCStr(ref e) => e.source(),
+            CStrError::BufferTooSmall(ref e) => e.source(),
+            CStrError::StrTab(ref e) => e.source(),
+            CStrError::Ascii(ref e) => e.source(),
+            #[cfg(feature = "alloc")]
+            CStrError::Alloc(ref e) => e.source(),
+            #[cfg(feature = "alloc")]
+            CStrError::SecretNul(ref e) => e.source(),
+        }
+    }
+}
+
 impl CStr {
     /// Casts a raw C string to a safe C string wrapper.
     ///
@@ -1218,6 +1547,7 @@ mod tests {
     use std::borrow::Cow::{Borrowed, Owned};
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
+    use std::string::ToString;
 
     #[test]
     fn c_to_rust() {
@@ -1341,6 +1671,31 @@ mod tests {
         assert!(cstr.is_err());
     }
 
+    #[test]
+    fn from_bytes_with_nul_error_kind() {
+        let err = CStr::from_bytes_with_nul(b"1\0ab\0").unwrap_err();
+        assert_eq!(err.kind(), FromBytesWithNulErrorKind::InteriorNul(1));
+        assert_eq!(err.nul_position(), Some(1));
+        let err = CStr::from_bytes_with_nul(b"123").unwrap_err();
+        assert_eq!(err.kind(), FromBytesWithNulErrorKind::NotNulTerminated);
+        assert_eq!(err.nul_position(), None);
+    }
+
+    #[test]
+    fn error_source() {
+        let err = CString::new(&b"\xff"[..]).unwrap().into_string().unwrap_err();
+        let utf8 = err.utf8_error();
+        let source = err.source().unwrap();
+        assert_eq!(source.to_string(), utf8.to_string());
+
+        let err = CStrError::from(err);
+        assert_eq!(err.to_string(), "C string contained non-utf8 bytes");
+        assert_eq!(err.source().unwrap().to_string(), utf8.to_string());
+
+        let err = CStrError::from(CString::new("a\0").unwrap_err());
+        assert!(err.source().is_none());
+    }
+
     #[test]
     fn into_boxed() {
         let orig: &[u8] = b"Hello, world!\0";
@@ -0,0 +1,215 @@
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use CStr;
+
+// States of the scanner, as used by glibc's `strverscmp`. Each state is a
+// multiple of 3 so that the class of the current byte can be added to it.
+const S_N: u8 = 0; // normal
+const S_I: u8 = 3; // comparing integral part
+const S_F: u8 = 6; // comparing fractional parts
+const S_Z: u8 = 9; // idem but with leading zeroes only
+
+// Result types, chosen to not collide with the `-1` and `+1` entries below.
+const CMP: i8 = 2;
+const LEN: i8 = 3;
+
+#[rustfmt::skip]
+static NEXT_STATE: [u8; 12] = [
+    /* state    x    d    0  */
+    /* S_N */  S_N, S_I, S_Z,
+    /* S_I */  S_N, S_I, S_I,
+    /* S_F */  S_N, S_F, S_F,
+    /* S_Z */  S_N, S_F, S_Z,
+];
+
+#[rustfmt::skip]
+static RESULT_TYPE: [i8; 36] = [
+    /* state   x/x  x/d  x/0  d/x  d/d  d/0  0/x  0/d  0/0  */
+    /* S_N */  CMP, CMP, CMP, CMP, LEN, CMP, CMP, CMP, CMP,
+    /* S_I */  CMP, -1,  -1,  1,   LEN, LEN, 1,   LEN, LEN,
+    /* S_F */  CMP, CMP, CMP, CMP, CMP, CMP, CMP, CMP, CMP,
+    /* S_Z */  CMP, 1,   1,   -1,  CMP, CMP, -1,  CMP, CMP,
+];
+
+/// Classifies a byte as a non-digit (0), a non-zero digit (1) or `'0'` (2).
+#[inline]
+fn class(c: u8) -> u8 {
+    (c == b'0') as u8 + c.is_ascii_digit() as u8
+}
+
+/// Compares two nul-terminated byte strings the way glibc's `strverscmp`
+/// does.
+///
+/// Both slices must end with their single nul terminator; this guarantees
+/// that every scan below stops before running off the end of the slice.
+fn strverscmp(s1: &[u8], s2: &[u8]) -> Ordering {
+    let (mut i1, mut i2) = (0, 0);
+    let mut c1 = s1[i1];
+    let mut c2 = s2[i2];
+    let mut state = S_N + class(c1);
+
+    while c1 == c2 {
+        if c1 == 0 {
+            return Ordering::Equal;
+        }
+        state = NEXT_STATE[state as usize];
+        i1 += 1;
+        i2 += 1;
+        c1 = s1[i1];
+        c2 = s2[i2];
+        state += class(c1);
+    }
+
+    let diff = c1.cmp(&c2);
+    match RESULT_TYPE[state as usize * 3 + class(c2) as usize] {
+        CMP => diff,
+        LEN => {
+            // Whichever run of digits is longer is the bigger number.
+            i1 += 1;
+            i2 += 1;
+            while s1[i1].is_ascii_digit() {
+                if !s2[i2].is_ascii_digit() {
+                    return Ordering::Greater;
+                }
+                i1 += 1;
+                i2 += 1;
+            }
+            if s2[i2].is_ascii_digit() {
+                Ordering::Less
+            } else {
+                diff
+            }
+        }
+        r => r.cmp(&0),
+    }
+}
+
+impl CStr {
+    /// Compares two C strings using "natural" version ordering.
+    ///
+    /// This follows the semantics of glibc's `strverscmp`: runs of decimal
+    /// digits are compared by their numeric value, so that `sda2` sorts before
+    /// `sda10`, while digit runs with leading zeroes are treated as fractional
+    /// parts, so that `1.01` sorts before `1.1`. All other bytes are compared
+    /// as in the byte-wise [`Ord`] implementation.
+    ///
+    /// [`Ord`]: https://doc.rust-lang.org/core/cmp/trait.Ord.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use cstr_core::CStr;
+    ///
+    /// let a = CStr::from_bytes_with_nul(b"sda2\0").unwrap();
+    /// let b = CStr::from_bytes_with_nul(b"sda10\0").unwrap();
+    /// assert_eq!(a.natural_cmp(b), Ordering::Less);
+    /// assert_eq!(a.cmp(b), Ordering::Greater);
+    /// ```
+    pub fn natural_cmp(&self, other: &CStr) -> Ordering {
+        strverscmp(self.to_bytes_with_nul(), other.to_bytes_with_nul())
+    }
+}
+
+/// A wrapper which orders C strings using [`CStr::natural_cmp`].
+///
+/// This is useful to sort collections, or to use C strings as keys in ordered
+/// maps, using version-aware ordering instead of plain byte ordering.
+///
+/// [`CStr::natural_cmp`]: struct.CStr.html#method.natural_cmp
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{CStr, NaturalOrder};
+///
+/// let mut names = vec![
+///     CStr::from_bytes_with_nul(b"sda10\0").unwrap(),
+///     CStr::from_bytes_with_nul(b"sda2\0").unwrap(),
+///     CStr::from_bytes_with_nul(b"sda1\0").unwrap(),
+/// ];
+/// names.sort_by_key(|&s| NaturalOrder(s));
+/// assert_eq!(names[0].to_bytes(), b"sda1");
+/// assert_eq!(names[1].to_bytes(), b"sda2");
+/// assert_eq!(names[2].to_bytes(), b"sda10");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NaturalOrder<T>(pub T);
+
+impl<T: AsRef<CStr>> PartialEq for NaturalOrder<T> {
+    #[inline]
+    fn eq(&self, other: &NaturalOrder<T>) -> bool {
+        self.0.as_ref() == other.0.as_ref()
+    }
+}
+
+impl<T: AsRef<CStr>> Eq for NaturalOrder<T> {}
+
+impl<T: AsRef<CStr>> PartialOrd for NaturalOrder<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &NaturalOrder<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: AsRef<CStr>> Ord for NaturalOrder<T> {
+    #[inline]
+    fn cmp(&self, other: &NaturalOrder<T>) -> Ordering {
+        self.0.as_ref().natural_cmp(other.0.as_ref())
+    }
+}
+
+impl<T: AsRef<CStr>> Hash for NaturalOrder<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.as_ref().hash(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn c(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    #[test]
+    fn glibc_examples() {
+        // The ordering documented in the glibc manual for `strverscmp`.
+        let sorted: [&[u8]; 9] = [
+            b"000\0", b"00\0", b"01\0", b"010\0", b"09\0", b"0\0", b"1\0", b"9\0", b"10\0",
+        ];
+        for (i, a) in sorted.iter().enumerate() {
+            for (j, b) in sorted.iter().enumerate() {
+                assert_eq!(c(a).natural_cmp(c(b)), i.cmp(&j), "{:?} vs {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn man_page_examples() {
+        assert_eq!(c(b"jan1\0").natural_cmp(c(b"jan10\0")), Ordering::Less);
+        assert_eq!(c(b"jan10\0").natural_cmp(c(b"jan1\0")), Ordering::Greater);
+        assert_eq!(c(b"jan10\0").natural_cmp(c(b"jan10\0")), Ordering::Equal);
+        assert_eq!(c(b"sda2\0").natural_cmp(c(b"sda10\0")), Ordering::Less);
+        assert_eq!(c(b"1.01\0").natural_cmp(c(b"1.1\0")), Ordering::Less);
+        assert_eq!(c(b"a\0").natural_cmp(c(b"b\0")), Ordering::Less);
+        assert_eq!(c(b"\0").natural_cmp(c(b"a\0")), Ordering::Less);
+        assert_eq!(c(b"abc12\0").natural_cmp(c(b"abc12x\0")), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_order_sort() {
+        let mut v = [
+            NaturalOrder(c(b"mod-1.10\0")),
+            NaturalOrder(c(b"mod-1.9\0")),
+            NaturalOrder(c(b"mod-1.2\0")),
+        ];
+        v.sort();
+        let v: Vec<_> = v.iter().map(|s| s.0.to_bytes()).collect();
+        assert_eq!(v, [&b"mod-1.2"[..], b"mod-1.9", b"mod-1.10"]);
+    }
+}
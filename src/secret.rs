@@ -0,0 +1,260 @@
+use alloc::vec::Vec;
+use core::error::Error;
+use core::sync::atomic::{self, Ordering};
+use core::{fmt, ops, ptr};
+
+use memchr::memchr;
+#[cfg(feature = "subtle")]
+use subtle::{Choice, ConstantTimeEq};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use {CStr, CString};
+
+/// Overwrites the whole allocation of `v`, including its spare capacity, with
+/// zeroes in a way the compiler cannot optimize out.
+fn wipe(v: &mut Vec<u8>) {
+    let ptr = v.as_mut_ptr();
+    for i in 0..v.capacity() {
+        unsafe { ptr::write_volatile(ptr.add(i), 0) };
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+/// An error indicating that a secret contained a nul byte.
+///
+/// Unlike [`NulError`], this does not hold on to the data: it is wiped
+/// before the error is returned. The position of the nul byte is available
+/// through [`nul_position`], but is left out of the `Debug` output.
+///
+/// [`NulError`]: struct.NulError.html
+/// [`nul_position`]: #method.nul_position
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SecretNulError(usize);
+
+impl SecretNulError {
+    /// Returns the position of the nul byte in the secret.
+    #[inline]
+    pub fn nul_position(&self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Debug for SecretNulError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretNulError(<redacted>)")
+    }
+}
+
+impl fmt::Display for SecretNulError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("nul byte found in secret data")
+    }
+}
+
+impl Error for SecretNulError {}
+
+/// An owned C string holding a secret, such as a password or a key.
+///
+/// Dropping a [`CString`] only clears its first byte. A `SecretCString`
+/// instead overwrites its whole buffer with volatile writes when it is
+/// dropped, and when its contents are moved to a larger buffer during
+/// construction. Its `Debug` output is redacted, and comparisons between
+/// secrets use [`CStr::ct_eq`].
+///
+/// If the `zeroize` feature is enabled, `SecretCString` also implements
+/// `Zeroize` and `ZeroizeOnDrop`, and if the `subtle` feature is enabled, it
+/// implements `ConstantTimeEq`.
+///
+/// [`CString`]: struct.CString.html
+/// [`CStr::ct_eq`]: struct.CStr.html#method.ct_eq
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::SecretCString;
+///
+/// let password = SecretCString::new("hunter2").unwrap();
+/// assert_eq!(password.to_bytes(), b"hunter2");
+/// assert_eq!(format!("{:?}", password), "SecretCString(<redacted>)");
+/// ```
+pub struct SecretCString {
+    /// The string, with its nul terminator.
+    inner: Vec<u8>,
+}
+
+impl SecretCString {
+    /// Creates a secret C string from a container of bytes.
+    ///
+    /// The bytes are moved into the new string without copying if the
+    /// vector has room for the nul terminator. Otherwise they are copied into
+    /// a new buffer and the old one is wiped.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SecretNulError`] if the bytes contain a nul byte, after
+    /// wiping them.
+    ///
+    /// [`SecretNulError`]: struct.SecretNulError.html
+    pub fn new<T: Into<Vec<u8>>>(t: T) -> Result<SecretCString, SecretNulError> {
+        let mut v = t.into();
+        if let Some(i) = memchr(0, &v) {
+            wipe(&mut v);
+            return Err(SecretNulError(i));
+        }
+        if v.len() == v.capacity() {
+            let mut grown = Vec::with_capacity(v.len() + 1);
+            grown.extend_from_slice(&v);
+            wipe(&mut v);
+            v = grown;
+        }
+        v.push(0);
+        Ok(SecretCString { inner: v })
+    }
+
+    /// Extracts a [`CStr`] slice containing the entire string.
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.inner) }
+    }
+}
+
+impl Drop for SecretCString {
+    #[inline]
+    fn drop(&mut self) {
+        wipe(&mut self.inner);
+    }
+}
+
+impl ops::Deref for SecretCString {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl AsRef<CStr> for SecretCString {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl Clone for SecretCString {
+    fn clone(&self) -> SecretCString {
+        let mut inner = Vec::with_capacity(self.inner.len());
+        inner.extend_from_slice(&self.inner);
+        SecretCString { inner }
+    }
+}
+
+impl Default for SecretCString {
+    #[inline]
+    fn default() -> SecretCString {
+        SecretCString {
+            inner: ::alloc::vec![0],
+        }
+    }
+}
+
+impl fmt::Debug for SecretCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretCString(<redacted>)")
+    }
+}
+
+/// Compares the strings with [`CStr::ct_eq`](struct.CStr.html#method.ct_eq).
+impl PartialEq for SecretCString {
+    #[inline]
+    fn eq(&self, other: &SecretCString) -> bool {
+        self.as_c_str().ct_eq(other.as_c_str())
+    }
+}
+
+impl Eq for SecretCString {}
+
+/// Takes over the buffer of the `CString` without copying it.
+impl From<CString> for SecretCString {
+    #[inline]
+    fn from(s: CString) -> SecretCString {
+        SecretCString {
+            inner: s.into_bytes_with_nul(),
+        }
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl ConstantTimeEq for SecretCString {
+    #[inline]
+    fn ct_eq(&self, other: &SecretCString) -> Choice {
+        self.as_c_str()
+            .to_bytes()
+            .ct_eq(other.as_c_str().to_bytes())
+    }
+}
+
+/// Wipes the string, leaving it empty.
+#[cfg(feature = "zeroize")]
+impl Zeroize for SecretCString {
+    fn zeroize(&mut self) {
+        wipe(&mut self.inner);
+        self.inner.truncate(1);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for SecretCString {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn new() {
+        let mut v = Vec::with_capacity(8);
+        v.extend_from_slice(b"pass");
+        let ptr = v.as_ptr();
+        let s = SecretCString::new(v).unwrap();
+        assert_eq!(s.to_bytes_with_nul(), b"pass\0");
+        assert_eq!(s.as_ptr() as *const u8, ptr);
+
+        let s = SecretCString::new(String::from("word")).unwrap();
+        assert_eq!(s.to_bytes(), b"word");
+        let err = SecretCString::new("p\0ss").unwrap_err();
+        assert_eq!(err.nul_position(), 1);
+        assert_eq!(format!("{:?}", err), "SecretNulError(<redacted>)");
+        assert_eq!(SecretCString::default().to_bytes(), b"");
+    }
+
+    #[test]
+    fn wipe_buffer() {
+        let mut v = ::alloc::vec![1, 2, 3];
+        v.truncate(1);
+        wipe(&mut v);
+        unsafe { v.set_len(3) };
+        assert_eq!(v, [0, 0, 0]);
+    }
+
+    #[test]
+    fn eq() {
+        let a = SecretCString::new("token").unwrap();
+        assert_eq!(a, a.clone());
+        assert_eq!(a, SecretCString::from(CString::new("token").unwrap()));
+        assert!(a != SecretCString::new("tokem").unwrap());
+        assert!(a != SecretCString::new("toke").unwrap());
+        assert_eq!(format!("{:?}", a), "SecretCString(<redacted>)");
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize() {
+        let mut s = SecretCString::new("key").unwrap();
+        s.zeroize();
+        assert_eq!(s.to_bytes_with_nul(), b"\0");
+    }
+}
@@ -0,0 +1,417 @@
+//! Serialization support for `CStr` and `CString`, enabled by the `serde`
+//! feature.
+//!
+//! By default C strings are serialized as byte strings, without their nul
+//! terminator. The submodules of this module can be used with
+//! `#[serde(with = "...")]` to pick a different representation:
+//!
+//! - [`bytes_with_nul`] serializes the nul terminator as well, which allows
+//!   formats that can borrow byte strings to deserialize into a `&CStr`.
+//! - [`str`] serializes as a UTF-8 string, failing on invalid UTF-8.
+//! - [`str_lossy`] serializes as a UTF-8 string, replacing invalid sequences
+//!   with `U+FFFD REPLACEMENT CHARACTER`.
+//!
+//! Deserialization accepts both strings and byte strings, with or without a
+//! trailing nul terminator, but rejects interior nul bytes.
+//!
+//! [`bytes_with_nul`]: bytes_with_nul/index.html
+//! [`str`]: str/index.html
+//! [`str_lossy`]: str_lossy/index.html
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate cstr_core;
+//! # #[macro_use] extern crate serde_derive;
+//! use cstr_core::CString;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "cstr_core::serde::str")]
+//!     name: CString,
+//!     raw: CString,
+//! }
+//! # fn main() {}
+//! ```
+
+use core::fmt;
+
+use serde_crate::de::{self, Deserialize, Deserializer, Visitor};
+use serde_crate::ser::{self, Serialize, Serializer};
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use CStr;
+#[cfg(feature = "alloc")]
+use CString;
+
+/// Writes the bytes of the string without its nul terminator.
+///
+/// The result cannot be deserialized back into a borrowed `&CStr`, which
+/// needs the nul terminator to be present in the input. Use
+/// [`bytes_with_nul`](bytes_with_nul/index.html) for data which should be
+/// borrowed when deserializing.
+impl Serialize for CStr {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.to_bytes())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Serialize for CString {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+struct BorrowedCStrVisitor;
+
+impl<'de> Visitor<'de> for BorrowedCStrVisitor {
+    type Value = &'de CStr;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a borrowed nul-terminated string")
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<&'de CStr, E> {
+        CStr::from_bytes_with_nul(v).map_err(E::custom)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<&'de CStr, E> {
+        self.visit_borrowed_bytes(v.as_bytes())
+    }
+}
+
+/// Borrows a `CStr` directly from the input.
+///
+/// This only works with formats which can hand out borrowed byte strings, and
+/// only if the serialized data includes the nul terminator, as written by
+/// [`bytes_with_nul`](bytes_with_nul/index.html). In particular, the output of
+/// the `Serialize` impl of `CStr`, which leaves out the nul terminator, is
+/// rejected: the borrowed input has no room for the terminator to be added.
+impl<'de: 'a, 'a> Deserialize<'de> for &'a CStr {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<&'a CStr, D::Error> {
+        deserializer.deserialize_bytes(BorrowedCStrVisitor)
+    }
+}
+
+/// Builds a `CString` out of deserialized bytes, dropping an optional nul
+/// terminator.
+///
+/// Only a single trailing nul byte is dropped, so that both representations
+/// written by this module are accepted. Any other nul byte, including a
+/// second trailing one, is rejected as an interior nul byte.
+#[cfg(feature = "alloc")]
+fn c_string_from_vec<E: de::Error>(mut v: Vec<u8>) -> Result<CString, E> {
+    if v.last() == Some(&0) {
+        v.pop();
+    }
+    CString::new(v).map_err(E::custom)
+}
+
+#[cfg(feature = "alloc")]
+struct CStringVisitor {
+    utf8_only: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Visitor<'de> for CStringVisitor {
+    type Value = CString;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.utf8_only {
+            f.write_str("a string without interior nul bytes")
+        } else {
+            f.write_str("a byte string without interior nul bytes")
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<CString, E> {
+        c_string_from_vec(v.into())
+    }
+
+    fn visit_string<E: de::Error>(self, v: ::alloc::string::String) -> Result<CString, E> {
+        c_string_from_vec(v.into_bytes())
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<CString, E> {
+        self.visit_byte_buf(v.into())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<CString, E> {
+        if self.utf8_only && ::core::str::from_utf8(&v).is_err() {
+            return Err(E::invalid_value(de::Unexpected::Bytes(&v), &self));
+        }
+        c_string_from_vec(v)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<CString, A::Error> {
+        let mut v = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            v.push(b);
+        }
+        self.visit_byte_buf(v)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for CString {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CString, D::Error> {
+        deserializer.deserialize_byte_buf(CStringVisitor { utf8_only: false })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for Box<CStr> {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Box<CStr>, D::Error> {
+        CString::deserialize(deserializer).map(CString::into_boxed_c_str)
+    }
+}
+
+/// Writes a byte string, replacing invalid UTF-8 sequences with
+/// `U+FFFD REPLACEMENT CHARACTER`.
+struct Lossy<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Lossy<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.0.utf8_chunks() {
+            f.write_str(chunk.valid())?;
+            if !chunk.invalid().is_empty() {
+                f.write_str("\u{FFFD}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serializes a C string as a byte string including its nul terminator.
+///
+/// Use with `#[serde(with = "cstr_core::serde::bytes_with_nul")]`.
+pub mod bytes_with_nul {
+    use super::*;
+
+    /// Serializes `value` as a byte string including its nul terminator.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<CStr>,
+        S: Serializer,
+    {
+        serializer.serialize_bytes(value.as_ref().to_bytes_with_nul())
+    }
+
+    /// Deserializes an owned C string from a byte string or a string.
+    #[cfg(feature = "alloc")]
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<CString>,
+        D: Deserializer<'de>,
+    {
+        CString::deserialize(deserializer).map(T::from)
+    }
+}
+
+/// Serializes a C string as a UTF-8 string, failing if it is not valid UTF-8.
+///
+/// Use with `#[serde(with = "cstr_core::serde::str")]`.
+pub mod str {
+    use super::*;
+
+    /// Serializes `value` as a string, failing if it is not valid UTF-8.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<CStr>,
+        S: Serializer,
+    {
+        match value.as_ref().to_str() {
+            Ok(s) => serializer.serialize_str(s),
+            Err(e) => Err(ser::Error::custom(e)),
+        }
+    }
+
+    /// Deserializes an owned C string from a string, rejecting byte strings
+    /// which are not valid UTF-8.
+    #[cfg(feature = "alloc")]
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<CString>,
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_string(CStringVisitor { utf8_only: true })
+            .map(T::from)
+    }
+}
+
+/// Serializes a C string as a UTF-8 string, replacing invalid sequences with
+/// `U+FFFD REPLACEMENT CHARACTER`.
+///
+/// Use with `#[serde(with = "cstr_core::serde::str_lossy")]`.
+pub mod str_lossy {
+    use super::*;
+
+    /// Serializes `value` as a string, replacing invalid UTF-8 sequences.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<CStr>,
+        S: Serializer,
+    {
+        let bytes = value.as_ref().to_bytes();
+        match ::core::str::from_utf8(bytes) {
+            Ok(s) => serializer.serialize_str(s),
+            Err(_) => serializer.collect_str(&Lossy(bytes)),
+        }
+    }
+
+    /// Deserializes an owned C string from a string or a byte string.
+    #[cfg(feature = "alloc")]
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<CString>,
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_string(CStringVisitor { utf8_only: false })
+            .map(T::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_tokens, Token};
+
+    fn c(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    #[test]
+    fn bytes() {
+        let s = CString::new("foo").unwrap();
+        assert_tokens(&s, &[Token::Bytes(b"foo")]);
+        assert_de_tokens(&s, &[Token::ByteBuf(b"foo\0")]);
+        assert_de_tokens(&s, &[Token::Str("foo")]);
+        assert_de_tokens(
+            &s,
+            &[
+                Token::Seq { len: Some(3) },
+                Token::U8(b'f'),
+                Token::U8(b'o'),
+                Token::U8(b'o'),
+                Token::SeqEnd,
+            ],
+        );
+        assert_de_tokens(&s.clone().into_boxed_c_str(), &[Token::Bytes(b"foo")]);
+        assert_de_tokens_error::<CString>(
+            &[Token::Bytes(b"f\0oo")],
+            "nul byte found in provided data at position: 1",
+        );
+        assert_de_tokens_error::<CString>(
+            &[Token::Bytes(b"foo\0\0")],
+            "nul byte found in provided data at position: 3",
+        );
+    }
+
+    #[test]
+    fn borrowed() {
+        assert_de_tokens(&c(b"foo\0"), &[Token::BorrowedBytes(b"foo\0")]);
+        assert_de_tokens(&c(b"foo\0"), &[Token::BorrowedStr("foo\0")]);
+        assert_de_tokens_error::<&CStr>(
+            &[Token::BorrowedBytes(b"foo")],
+            "data provided is not nul terminated",
+        );
+    }
+
+    #[test]
+    fn borrowed_round_trip() {
+        use serde_test::assert_ser_tokens;
+
+        struct WithNul<'a>(&'a CStr);
+
+        impl<'a> Serialize for WithNul<'a> {
+            fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                bytes_with_nul::serialize(self.0, s)
+            }
+        }
+
+        // The default representation leaves out the nul terminator, which a
+        // borrowed `&CStr` cannot do without.
+        let s = c(b"foo\0");
+        assert_ser_tokens(&s, &[Token::Bytes(b"foo")]);
+        assert_de_tokens_error::<&CStr>(
+            &[Token::BorrowedBytes(b"foo")],
+            "data provided is not nul terminated",
+        );
+
+        assert_ser_tokens(&WithNul(s), &[Token::Bytes(b"foo\0")]);
+        assert_de_tokens(&s, &[Token::BorrowedBytes(b"foo\0")]);
+    }
+
+    // Defines a `Repr` newtype which uses the given module for its field, as
+    // `#[serde(with = "...")]` would.
+    macro_rules! repr_impls {
+        ($module:ident) => {
+            #[derive(Debug, PartialEq)]
+            struct Repr(CString);
+
+            impl Serialize for Repr {
+                fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                    $module::serialize(&self.0, s)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for Repr {
+                fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Repr, D::Error> {
+                    $module::deserialize(d).map(Repr)
+                }
+            }
+        };
+    }
+
+    mod with_nul {
+        use super::*;
+        repr_impls!(bytes_with_nul);
+
+        #[test]
+        fn bytes_with_nul() {
+            let s = Repr(CString::new("foo").unwrap());
+            assert_tokens(&s, &[Token::Bytes(b"foo\0")]);
+        }
+    }
+
+    mod strict {
+        use super::*;
+        repr_impls!(str);
+
+        #[test]
+        fn str() {
+            let s = Repr(CString::new("foo").unwrap());
+            assert_tokens(&s, &[Token::Str("foo")]);
+            assert_de_tokens_error::<Repr>(
+                &[Token::Bytes(b"\xff")],
+                "invalid value: byte array, expected a string without interior nul bytes",
+            );
+        }
+    }
+
+    mod lossy {
+        use super::*;
+        use serde_test::assert_ser_tokens;
+        repr_impls!(str_lossy);
+
+        #[test]
+        fn str_lossy() {
+            let s = Repr(CString::new("foo").unwrap());
+            assert_tokens(&s, &[Token::Str("foo")]);
+            let s = Repr(CString::new(&b"a\xffb"[..]).unwrap());
+            assert_ser_tokens(&s, &[Token::Str("a\u{FFFD}b")]);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use alloc::borrow::{Borrow, ToOwned};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::{fmt, ops};
+
+use memchr::memchr;
+
+use {CStr, CString, NulError};
+
+/// Number of bytes a [`SmallCString`] can store inline, including the nul
+/// terminator.
+///
+/// [`SmallCString`]: struct.SmallCString.html
+const INLINE_CAPACITY: usize = 24;
+
+#[derive(Clone)]
+enum Repr {
+    /// `buf[..len]` holds the string, followed by a nul terminator.
+    Inline {
+        len: u8,
+        buf: [u8; INLINE_CAPACITY],
+    },
+    Heap(CString),
+}
+
+/// An owned C string which stores short strings inline.
+///
+/// Strings of up to 23 bytes, plus their nul terminator, are stored within
+/// the `SmallCString` itself rather than in a separate heap allocation.
+/// Longer strings are stored in a [`CString`]. Converting a heap-backed
+/// `SmallCString` to and from a [`CString`] does not copy the string.
+///
+/// [`CString`]: struct.CString.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::SmallCString;
+///
+/// let key = SmallCString::new("user.name").unwrap();
+/// assert!(key.is_inline());
+/// assert_eq!(key.to_bytes(), b"user.name");
+///
+/// let long = SmallCString::new("a rather long string, which spills").unwrap();
+/// assert!(!long.is_inline());
+/// ```
+#[derive(Clone)]
+pub struct SmallCString {
+    repr: Repr,
+}
+
+impl SmallCString {
+    /// The number of bytes that can be stored inline, including the nul
+    /// terminator.
+    pub const INLINE_CAPACITY: usize = INLINE_CAPACITY;
+
+    /// Creates a C string by copying the given bytes.
+    ///
+    /// Unlike [`CString::new`], this always copies its argument, which avoids
+    /// any allocation when the string fits inline.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`NulError`] if the bytes contain a nul byte.
+    ///
+    /// [`CString::new`]: struct.CString.html#method.new
+    /// [`NulError`]: struct.NulError.html
+    pub fn new<T: AsRef<[u8]>>(t: T) -> Result<SmallCString, NulError> {
+        let bytes = t.as_ref();
+        match memchr(0, bytes) {
+            Some(pos) => Err(NulError(pos, bytes.to_vec())),
+            None => Ok(unsafe { SmallCString::from_bytes_unchecked(bytes) }),
+        }
+    }
+
+    /// Creates a C string from bytes which contain no nul byte.
+    unsafe fn from_bytes_unchecked(bytes: &[u8]) -> SmallCString {
+        if bytes.len() < INLINE_CAPACITY {
+            let mut buf = [0; INLINE_CAPACITY];
+            buf[..bytes.len()].copy_from_slice(bytes);
+            SmallCString {
+                repr: Repr::Inline {
+                    len: bytes.len() as u8,
+                    buf,
+                },
+            }
+        } else {
+            let mut v = Vec::with_capacity(bytes.len() + 1);
+            v.extend_from_slice(bytes);
+            SmallCString {
+                repr: Repr::Heap(CString::from_vec_unchecked(v)),
+            }
+        }
+    }
+
+    /// Returns whether the string is stored inline.
+    #[inline]
+    pub fn is_inline(&self) -> bool {
+        match self.repr {
+            Repr::Inline { .. } => true,
+            Repr::Heap(_) => false,
+        }
+    }
+
+    /// Extracts a [`CStr`] slice containing the entire string.
+    ///
+    /// [`CStr`]: struct.CStr.html
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        match self.repr {
+            Repr::Inline { len, ref buf } => unsafe {
+                CStr::from_bytes_with_nul_unchecked(&buf[..len as usize + 1])
+            },
+            Repr::Heap(ref s) => s,
+        }
+    }
+
+    /// Converts this string into a [`CString`].
+    ///
+    /// This only allocates if the string is stored inline.
+    ///
+    /// [`CString`]: struct.CString.html
+    #[inline]
+    pub fn into_c_string(self) -> CString {
+        match self.repr {
+            Repr::Inline { .. } => self.as_c_str().to_owned(),
+            Repr::Heap(s) => s,
+        }
+    }
+}
+
+impl ops::Deref for SmallCString {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl AsRef<CStr> for SmallCString {
+    #[inline]
+    fn as_ref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl Borrow<CStr> for SmallCString {
+    #[inline]
+    fn borrow(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl Default for SmallCString {
+    #[inline]
+    fn default() -> SmallCString {
+        SmallCString {
+            repr: Repr::Inline {
+                len: 0,
+                buf: [0; INLINE_CAPACITY],
+            },
+        }
+    }
+}
+
+impl fmt::Debug for SmallCString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl<'a> From<&'a CStr> for SmallCString {
+    #[inline]
+    fn from(s: &'a CStr) -> SmallCString {
+        unsafe { SmallCString::from_bytes_unchecked(s.to_bytes()) }
+    }
+}
+
+/// Keeps the string on the heap, without copying it.
+impl From<CString> for SmallCString {
+    #[inline]
+    fn from(s: CString) -> SmallCString {
+        SmallCString {
+            repr: Repr::Heap(s),
+        }
+    }
+}
+
+impl From<SmallCString> for CString {
+    #[inline]
+    fn from(s: SmallCString) -> CString {
+        s.into_c_string()
+    }
+}
+
+impl PartialEq for SmallCString {
+    #[inline]
+    fn eq(&self, other: &SmallCString) -> bool {
+        self.as_c_str() == other.as_c_str()
+    }
+}
+
+impl Eq for SmallCString {}
+
+impl PartialOrd for SmallCString {
+    #[inline]
+    fn partial_cmp(&self, other: &SmallCString) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallCString {
+    #[inline]
+    fn cmp(&self, other: &SmallCString) -> Ordering {
+        self.as_c_str().cmp(other.as_c_str())
+    }
+}
+
+impl Hash for SmallCString {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_c_str().hash(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn inline_threshold() {
+        let max = [b'a'; INLINE_CAPACITY - 1];
+        let s = SmallCString::new(&max[..]).unwrap();
+        assert!(s.is_inline());
+        assert_eq!(s.to_bytes(), &max[..]);
+
+        let long = [b'a'; INLINE_CAPACITY];
+        let s = SmallCString::new(&long[..]).unwrap();
+        assert!(!s.is_inline());
+        assert_eq!(s.to_bytes_with_nul().len(), INLINE_CAPACITY + 1);
+
+        assert!(SmallCString::default().is_inline());
+        assert_eq!(SmallCString::default().to_bytes(), b"");
+        assert_eq!(SmallCString::new("ab\0c").unwrap_err().nul_position(), 2);
+    }
+
+    #[test]
+    fn c_string_conversions() {
+        let c = CString::new(Vec::from(&b"heap"[..])).unwrap();
+        let ptr = c.as_ptr();
+        let s = SmallCString::from(c);
+        assert!(!s.is_inline());
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(CString::from(s).as_ptr(), ptr);
+
+        let s = SmallCString::from(&*CString::new("inline").unwrap());
+        assert!(s.is_inline());
+        let c = s.clone().into_c_string();
+        assert_eq!(c.as_c_str(), s.as_c_str());
+        assert_eq!(SmallCString::from(c), s);
+        assert_eq!(format!("{:?}", s), r#""inline""#);
+    }
+}
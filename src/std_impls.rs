@@ -0,0 +1,136 @@
+//! Bridges to the Unix `OsStr` and `Path` types of `std`.
+//!
+//! `std::ffi::CStr` and `std::ffi::CString` are re-exports of
+//! `core::ffi::CStr` and `alloc::ffi::CString`, so the conversions for those
+//! are provided unconditionally alongside the `core` ones. Likewise the error
+//! types implement `core::error::Error`, which `std::error::Error` re-exports.
+
+use std::convert::TryFrom;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use {CStr, CString, NulError};
+
+impl AsRef<OsStr> for CStr {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        OsStr::from_bytes(self.to_bytes())
+    }
+}
+
+impl AsRef<OsStr> for CString {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        OsStr::from_bytes(self.as_bytes())
+    }
+}
+
+impl AsRef<Path> for CStr {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        Path::new(OsStr::from_bytes(self.to_bytes()))
+    }
+}
+
+impl AsRef<Path> for CString {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        Path::new(OsStr::from_bytes(self.as_bytes()))
+    }
+}
+
+impl From<CString> for OsString {
+    #[inline]
+    fn from(s: CString) -> OsString {
+        OsString::from_vec(s.into_bytes())
+    }
+}
+
+impl From<CString> for PathBuf {
+    #[inline]
+    fn from(s: CString) -> PathBuf {
+        PathBuf::from(OsString::from(s))
+    }
+}
+
+impl<'a> TryFrom<&'a OsStr> for CString {
+    type Error = NulError;
+
+    /// Creates a `CString` from an `OsStr`, failing if it contains a nul byte.
+    #[inline]
+    fn try_from(s: &'a OsStr) -> Result<CString, NulError> {
+        CString::new(s.as_bytes())
+    }
+}
+
+impl TryFrom<OsString> for CString {
+    type Error = NulError;
+
+    /// Creates a `CString` from an `OsString`, reusing its buffer, and
+    /// failing if it contains a nul byte.
+    #[inline]
+    fn try_from(s: OsString) -> Result<CString, NulError> {
+        CString::new(s.into_vec())
+    }
+}
+
+impl<'a> TryFrom<&'a Path> for CString {
+    type Error = NulError;
+
+    /// Creates a `CString` from a `Path`, failing if it contains a nul byte.
+    #[inline]
+    fn try_from(s: &'a Path) -> Result<CString, NulError> {
+        CString::try_from(s.as_os_str())
+    }
+}
+
+impl TryFrom<PathBuf> for CString {
+    type Error = NulError;
+
+    /// Creates a `CString` from a `PathBuf`, reusing its buffer, and failing
+    /// if it contains a nul byte.
+    #[inline]
+    fn try_from(s: PathBuf) -> Result<CString, NulError> {
+        CString::try_from(s.into_os_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::boxed::Box;
+    use std::error::Error;
+    use std::ffi;
+    use std::string::ToString;
+
+    #[test]
+    fn std_ffi() {
+        let theirs = ffi::CString::new("hello").unwrap();
+        let ours: &CStr = theirs.as_c_str().into();
+        assert_eq!(ours, theirs.as_c_str());
+        let owned = CString::from(ours);
+        assert_eq!(CString::from(theirs), owned);
+    }
+
+    #[test]
+    fn os_str() {
+        let s = CString::new("/tmp/foo").unwrap();
+        assert_eq!(AsRef::<OsStr>::as_ref(&*s), OsStr::new("/tmp/foo"));
+        assert_eq!(AsRef::<Path>::as_ref(&s), Path::new("/tmp/foo"));
+        assert_eq!(OsString::from(s.clone()), OsString::from("/tmp/foo"));
+        assert_eq!(PathBuf::from(s.clone()), PathBuf::from("/tmp/foo"));
+
+        assert_eq!(CString::try_from(Path::new("/tmp/foo")).unwrap(), s);
+        assert_eq!(CString::try_from(PathBuf::from("/tmp/foo")).unwrap(), s);
+        let err = CString::try_from(OsStr::new("/tmp\0/foo")).unwrap_err();
+        assert_eq!(err.nul_position(), 4);
+        assert!(CString::try_from(OsString::from("\0")).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let err: Box<dyn Error> = Box::new(CString::new("a\0b").unwrap_err());
+        assert_eq!(err.to_string(), "nul byte found in provided data at position: 1");
+    }
+}
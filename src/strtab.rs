@@ -0,0 +1,463 @@
+#[cfg(feature = "alloc")]
+use alloc::borrow::ToOwned;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::iter::FusedIterator;
+
+use memchr::memchr;
+
+#[cfg(feature = "alloc")]
+use CString;
+use {CStr, CStrs};
+
+/// A table of nul-terminated strings referenced by byte offset.
+///
+/// This is the layout of ELF `.strtab` and `.dynstr` sections, DWARF
+/// `.debug_str` and many other binary formats: strings are stored back to
+/// back, and are referred to by the offset of their first byte. Lookups never
+/// read past the end of the table.
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::StrTab;
+///
+/// let table = StrTab::new(b"\0main\0printf\0");
+/// assert_eq!(table.get(1).unwrap().to_bytes(), b"main");
+/// // Offsets may point into the middle of a string.
+/// assert_eq!(table.get(9).unwrap().to_bytes(), b"ntf");
+/// assert!(table.get(13).is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct StrTab<'a> {
+    bytes: &'a [u8],
+}
+
+/// An error returned from [`StrTab::get`].
+///
+/// [`StrTab::get`]: struct.StrTab.html#method.get
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StrTabError {
+    /// The offset is past the end of the table.
+    OutOfBounds(usize),
+    /// No nul terminator was found between the offset and the end of the
+    /// table.
+    NotNulTerminated(usize),
+}
+
+impl StrTabError {
+    /// Returns the offset which was looked up.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        match *self {
+            StrTabError::OutOfBounds(offset) | StrTabError::NotNulTerminated(offset) => offset,
+        }
+    }
+}
+
+impl fmt::Display for StrTabError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StrTabError::OutOfBounds(offset) => {
+                write!(f, "string table offset {} is out of bounds", offset)
+            }
+            StrTabError::NotNulTerminated(offset) => {
+                write!(f, "string at table offset {} is not nul terminated", offset)
+            }
+        }
+    }
+}
+
+impl Error for StrTabError {}
+
+impl<'a> StrTab<'a> {
+    /// Wraps the raw bytes of a string table.
+    #[inline]
+    pub const fn new(bytes: &'a [u8]) -> StrTab<'a> {
+        StrTab { bytes }
+    }
+
+    /// Returns the raw bytes of the table.
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the size of the table in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns whether the table is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the string starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StrTabError::OutOfBounds`] if `offset` is not within the
+    /// table, and [`StrTabError::NotNulTerminated`] if the table ends before
+    /// the string does.
+    ///
+    /// [`StrTabError::OutOfBounds`]: enum.StrTabError.html#variant.OutOfBounds
+    /// [`StrTabError::NotNulTerminated`]: enum.StrTabError.html#variant.NotNulTerminated
+    pub fn get(&self, offset: usize) -> Result<&'a CStr, StrTabError> {
+        let bytes = match self.bytes.get(offset..) {
+            Some(bytes) if !bytes.is_empty() => bytes,
+            _ => return Err(StrTabError::OutOfBounds(offset)),
+        };
+        match memchr(0, bytes) {
+            Some(nul) => Ok(unsafe { CStr::from_bytes_with_nul_unchecked(&bytes[..nul + 1]) }),
+            None => Err(StrTabError::NotNulTerminated(offset)),
+        }
+    }
+
+    /// Returns an iterator over the strings of the table along with their
+    /// offsets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::StrTab;
+    ///
+    /// let table = StrTab::new(b"\0main\0printf\0");
+    /// let offsets: Vec<_> = table.iter().map(|(offset, _)| offset).collect();
+    /// assert_eq!(offsets, [0, 1, 6]);
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> StrTabIter<'a> {
+        StrTabIter {
+            inner: CStr::iter_from_bytes(self.bytes),
+            offset: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for StrTab<'a> {
+    type Item = (usize, &'a CStr);
+    type IntoIter = StrTabIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> StrTabIter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the strings of a [`StrTab`] and their offsets.
+///
+/// This `struct` is created by [`StrTab::iter`].
+///
+/// [`StrTab`]: struct.StrTab.html
+/// [`StrTab::iter`]: struct.StrTab.html#method.iter
+#[derive(Clone, Debug)]
+pub struct StrTabIter<'a> {
+    inner: CStrs<'a>,
+    offset: usize,
+}
+
+impl<'a> StrTabIter<'a> {
+    /// Returns the bytes which have not been consumed by the iterator yet.
+    ///
+    /// See [`CStrs::remainder`](struct.CStrs.html#method.remainder).
+    #[inline]
+    pub fn remainder(&self) -> &'a [u8] {
+        self.inner.remainder()
+    }
+}
+
+impl<'a> Iterator for StrTabIter<'a> {
+    type Item = (usize, &'a CStr);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, &'a CStr)> {
+        let s = self.inner.next()?;
+        let offset = self.offset;
+        self.offset += s.to_bytes_with_nul().len();
+        Some((offset, s))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> FusedIterator for StrTabIter<'a> {}
+
+/// A builder for string tables, the inverse of [`StrTab`].
+///
+/// Strings are added one at a time, and laid out in a single buffer by
+/// [`finish`]. Identical strings are stored once, and a string which is a
+/// suffix of another one shares its storage: adding both `"printf"` and
+/// `"f"` only stores `printf\0`, with `"f"` pointing at its last character.
+///
+/// Offsets are only known once the table is finished. [`add`] returns an
+/// index which can then be resolved with [`FinishedStrTab::offset`].
+///
+/// [`StrTab`]: struct.StrTab.html
+/// [`finish`]: #method.finish
+/// [`add`]: #method.add
+/// [`FinishedStrTab::offset`]: struct.FinishedStrTab.html#method.offset
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{CStr, StrTabBuilder};
+///
+/// let c = |b| CStr::from_bytes_with_nul(b).unwrap();
+///
+/// let mut builder = StrTabBuilder::with_leading_nul();
+/// let printf = builder.add(c(b"printf\0"));
+/// let f = builder.add(c(b"f\0"));
+/// let main = builder.add(c(b"main\0"));
+/// assert_eq!(builder.add(c(b"printf\0")), printf);
+///
+/// let table = builder.finish();
+/// assert_eq!(table.len(), 13);
+/// assert_eq!(table.offset(f), table.offset(printf) + 5);
+/// assert_eq!(table.str_tab().get(table.offset(main)), Ok(c(b"main\0")));
+/// assert_eq!(table.offset_of(c(b"main\0")), Some(table.offset(main)));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Default, Debug)]
+pub struct StrTabBuilder {
+    indices: BTreeMap<CString, usize>,
+    leading_nul: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl StrTabBuilder {
+    /// Creates an empty builder.
+    #[inline]
+    pub fn new() -> StrTabBuilder {
+        StrTabBuilder::default()
+    }
+
+    /// Creates an empty builder for a table starting with a nul byte.
+    ///
+    /// This is required by ELF, where offset 0 always refers to the empty
+    /// string. The empty string is then always laid out at offset 0.
+    #[inline]
+    pub fn with_leading_nul() -> StrTabBuilder {
+        StrTabBuilder {
+            indices: BTreeMap::new(),
+            leading_nul: true,
+        }
+    }
+
+    /// Adds a string to the table, and returns its index.
+    ///
+    /// Adding the same string several times returns the same index. Indices
+    /// are assigned in order starting from 0.
+    pub fn add(&mut self, s: &CStr) -> usize {
+        if let Some(&index) = self.indices.get(s) {
+            return index;
+        }
+        let index = self.indices.len();
+        self.indices.insert(s.to_owned(), index);
+        index
+    }
+
+    /// Returns the number of distinct strings added to the table.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns whether no string was added to the table.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Lays out the strings and returns the finished table.
+    pub fn finish(self) -> FinishedStrTab {
+        // Sorting the reversed strings in descending order puts every string
+        // right after one it is a suffix of, if there is any.
+        let mut strings: Vec<(&[u8], usize)> = self
+            .indices
+            .iter()
+            .map(|(s, &index)| (s.as_bytes(), index))
+            .collect();
+        strings.sort_unstable_by(|a, b| b.0.iter().rev().cmp(a.0.iter().rev()));
+
+        let mut bytes = Vec::new();
+        if self.leading_nul {
+            bytes.push(0);
+        }
+        let mut offsets = ::alloc::vec![0; strings.len()];
+        let mut prev: (&[u8], usize) = (&[], 0);
+        for (s, index) in strings {
+            let offset = if s.is_empty() && self.leading_nul {
+                0
+            } else if prev.0.ends_with(s) && !prev.0.is_empty() {
+                prev.1 + prev.0.len() - s.len()
+            } else {
+                let offset = bytes.len();
+                bytes.extend_from_slice(s);
+                bytes.push(0);
+                prev = (s, offset);
+                offset
+            };
+            offsets[index] = offset;
+        }
+
+        FinishedStrTab {
+            bytes,
+            offsets,
+            indices: self.indices,
+        }
+    }
+}
+
+/// A string table laid out by [`StrTabBuilder::finish`].
+///
+/// [`StrTabBuilder::finish`]: struct.StrTabBuilder.html#method.finish
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct FinishedStrTab {
+    bytes: Vec<u8>,
+    offsets: Vec<usize>,
+    indices: BTreeMap<CString, usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl FinishedStrTab {
+    /// Returns the bytes of the table.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the bytes of the table, consuming it.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the size of the table in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns whether the table is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns a reader over the table.
+    #[inline]
+    pub fn str_tab(&self) -> StrTab<'_> {
+        StrTab::new(&self.bytes)
+    }
+
+    /// Returns the offset of the string with the given index, as returned by
+    /// [`StrTabBuilder::add`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` was not returned by the builder of this table.
+    ///
+    /// [`StrTabBuilder::add`]: struct.StrTabBuilder.html#method.add
+    #[inline]
+    pub fn offset(&self, index: usize) -> usize {
+        self.offsets[index]
+    }
+
+    /// Returns the offset of the given string, or `None` if it was not added
+    /// to the table.
+    #[inline]
+    pub fn offset_of(&self, s: &CStr) -> Option<usize> {
+        self.indices.get(s).map(|&index| self.offsets[index])
+    }
+
+    /// Returns the offsets of all strings, indexed by the values returned by
+    /// [`StrTabBuilder::add`].
+    ///
+    /// [`StrTabBuilder::add`]: struct.StrTabBuilder.html#method.add
+    #[inline]
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    const TABLE: &[u8] = b"\0foo\0bar\0\0baz";
+
+    #[test]
+    fn get() {
+        let table = StrTab::new(TABLE);
+        assert_eq!(table.get(0).unwrap().to_bytes(), b"");
+        assert_eq!(table.get(1).unwrap().to_bytes(), b"foo");
+        assert_eq!(table.get(3).unwrap().to_bytes(), b"o");
+        assert_eq!(table.get(5).unwrap().to_bytes(), b"bar");
+        assert_eq!(table.get(9).unwrap().to_bytes(), b"");
+        assert_eq!(table.get(10), Err(StrTabError::NotNulTerminated(10)));
+        assert_eq!(table.get(13), Err(StrTabError::OutOfBounds(13)));
+        assert_eq!(table.get(usize::MAX).unwrap_err().offset(), usize::MAX);
+        assert!(StrTab::new(b"").get(0).is_err());
+    }
+
+    #[test]
+    fn iter() {
+        let table = StrTab::new(TABLE);
+        let mut iter = table.iter();
+        let v: Vec<_> = iter.by_ref().map(|(o, s)| (o, s.to_bytes())).collect();
+        assert_eq!(v, [(0, &b""[..]), (1, b"foo"), (5, b"bar"), (9, b"")]);
+        assert_eq!(iter.remainder(), b"baz");
+        for (offset, s) in table {
+            assert_eq!(table.get(offset), Ok(s));
+        }
+    }
+
+    fn c(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    #[test]
+    fn builder() {
+        assert!(StrTabBuilder::new().finish().is_empty());
+
+        let words: &[&[u8]] = &[b"b\0", b"ab\0", b"cab\0", b"x\0", b"b\0", b"\0", b"bx\0"];
+        let mut builder = StrTabBuilder::new();
+        let indices: Vec<_> = words.iter().map(|w| builder.add(c(w))).collect();
+        assert_eq!(indices, [0, 1, 2, 3, 0, 4, 5]);
+        assert_eq!(builder.len(), 6);
+
+        let table = builder.finish();
+        assert_eq!(table.len(), b"cab\0bx\0".len());
+        for (&w, &index) in words.iter().zip(&indices) {
+            assert_eq!(table.str_tab().get(table.offset(index)), Ok(c(w)));
+            assert_eq!(table.offset_of(c(w)), Some(table.offset(index)));
+        }
+        assert_eq!(table.offset_of(c(b"c\0")), None);
+        assert_eq!(table.offsets().len(), 6);
+    }
+
+    #[test]
+    fn builder_leading_nul() {
+        let table = StrTabBuilder::with_leading_nul().finish();
+        assert_eq!(table.into_bytes(), b"\0");
+
+        let mut builder = StrTabBuilder::with_leading_nul();
+        let foo = builder.add(c(b"foo\0"));
+        let empty = builder.add(c(b"\0"));
+        let table = builder.finish();
+        assert_eq!(table.as_bytes(), b"\0foo\0");
+        assert_eq!((table.offset(empty), table.offset(foo)), (0, 1));
+    }
+}
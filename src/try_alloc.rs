@@ -0,0 +1,285 @@
+//! Fallible-allocation counterparts of the `CString` constructors.
+//!
+//! `Rc<CStr>` and `Arc<CStr>` have no fallible constructors here: the
+//! standard library offers no way to allocate a reference-counted slice
+//! without aborting on failure, even on nightly, and their layout cannot be
+//! built by hand. `ArcCString::try_from_c_str` is the fallible alternative.
+
+use alloc::boxed::Box;
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+use memchr::memchr;
+
+use {CStr, CString, NulError};
+
+/// An error indicating that a memory allocation failed.
+///
+/// This is returned by the `try_*` constructors, such as
+/// [`CString::try_clone`], instead of aborting the process.
+///
+/// There are no fallible conversions into `Rc<CStr>` or `Arc<CStr>`, since
+/// the standard library cannot allocate a reference-counted slice without
+/// aborting on failure. [`ArcCString::try_from_c_str`] can be used instead.
+///
+/// [`CString::try_clone`]: struct.CString.html#method.try_clone
+/// [`ArcCString::try_from_c_str`]: struct.ArcCString.html#method.try_from_c_str
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AllocError(());
+
+impl AllocError {
+    #[inline]
+    pub(crate) fn new() -> AllocError {
+        AllocError(())
+    }
+}
+
+impl From<TryReserveError> for AllocError {
+    #[inline]
+    fn from(_: TryReserveError) -> AllocError {
+        AllocError::new()
+    }
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl Error for AllocError {}
+
+/// An error returned from [`CString::try_new`] and
+/// [`CString::try_from_vec`].
+///
+/// [`CString::try_new`]: struct.CString.html#method.try_new
+/// [`CString::try_from_vec`]: struct.CString.html#method.try_from_vec
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TryNewError {
+    /// The data contained a nul byte.
+    Nul(NulError),
+    /// Memory for the string could not be allocated.
+    Alloc(AllocError),
+}
+
+impl From<NulError> for TryNewError {
+    #[inline]
+    fn from(e: NulError) -> TryNewError {
+        TryNewError::Nul(e)
+    }
+}
+
+impl From<AllocError> for TryNewError {
+    #[inline]
+    fn from(e: AllocError) -> TryNewError {
+        TryNewError::Alloc(e)
+    }
+}
+
+impl From<TryReserveError> for TryNewError {
+    #[inline]
+    fn from(e: TryReserveError) -> TryNewError {
+        TryNewError::Alloc(e.into())
+    }
+}
+
+impl fmt::Display for TryNewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TryNewError::Nul(ref e) => e.fmt(f),
+            TryNewError::Alloc(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for TryNewError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            TryNewError::Nul(ref e) => Some(e),
+            TryNewError::Alloc(ref e) => Some(e),
+        }
+    }
+}
+
+/// Copies `bytes` into a new exactly-sized vector.
+fn try_copy(bytes: &[u8], capacity: usize) -> Result<Vec<u8>, AllocError> {
+    let mut v = Vec::new();
+    v.try_reserve_exact(capacity)?;
+    v.extend_from_slice(bytes);
+    Ok(v)
+}
+
+/// Converts a vector into a boxed slice, copying it if it has spare capacity
+/// rather than shrinking it in place, which aborts on failure.
+fn try_into_boxed_slice(v: Vec<u8>) -> Result<Box<[u8]>, AllocError> {
+    if v.len() == v.capacity() {
+        Ok(v.into_boxed_slice())
+    } else {
+        Ok(try_copy(&v, v.len())?.into_boxed_slice())
+    }
+}
+
+impl CString {
+    /// Creates a C string by copying the given bytes, without aborting on
+    /// allocation failure.
+    ///
+    /// Unlike [`new`], this takes the bytes by reference: converting them
+    /// into a `Vec<u8>` first could abort. See [`try_from_vec`] to reuse an
+    /// existing vector.
+    ///
+    /// [`new`]: #method.new
+    /// [`try_from_vec`]: #method.try_from_vec
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryNewError::Nul`] if the bytes contain a nul byte, and
+    /// [`TryNewError::Alloc`] if memory could not be allocated.
+    ///
+    /// [`TryNewError::Nul`]: enum.TryNewError.html#variant.Nul
+    /// [`TryNewError::Alloc`]: enum.TryNewError.html#variant.Alloc
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::{CString, TryNewError};
+    ///
+    /// let s = CString::try_new("foo").unwrap();
+    /// assert_eq!(s.as_bytes(), b"foo");
+    ///
+    /// match CString::try_new("f\0o") {
+    ///     Err(TryNewError::Nul(e)) => assert_eq!(e.nul_position(), 1),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn try_new<T: AsRef<[u8]>>(t: T) -> Result<CString, TryNewError> {
+        let bytes = t.as_ref();
+        let mut v = try_copy(bytes, bytes.len() + 1)?;
+        if let Some(i) = memchr(0, bytes) {
+            return Err(NulError(i, v).into());
+        }
+        v.push(0);
+        Ok(CString {
+            inner: try_into_boxed_slice(v)?,
+        })
+    }
+
+    /// Creates a C string from a vector of bytes, without aborting on
+    /// allocation failure.
+    ///
+    /// The allocation of the vector is reused if it has exactly enough room
+    /// for the nul terminator. Otherwise the string is copied into a new
+    /// allocation, as shrinking a vector in place aborts on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryNewError::Nul`] if the bytes contain a nul byte, and
+    /// [`TryNewError::Alloc`] if memory could not be allocated.
+    ///
+    /// [`TryNewError::Nul`]: enum.TryNewError.html#variant.Nul
+    /// [`TryNewError::Alloc`]: enum.TryNewError.html#variant.Alloc
+    pub fn try_from_vec(mut v: Vec<u8>) -> Result<CString, TryNewError> {
+        if let Some(i) = memchr(0, &v) {
+            return Err(NulError(i, v).into());
+        }
+        v.try_reserve_exact(1)?;
+        v.push(0);
+        Ok(CString {
+            inner: try_into_boxed_slice(v)?,
+        })
+    }
+
+    /// Clones the string, without aborting on allocation failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AllocError`] if memory could not be allocated.
+    ///
+    /// [`AllocError`]: struct.AllocError.html
+    #[inline]
+    pub fn try_clone(&self) -> Result<CString, AllocError> {
+        self.as_c_str().try_to_owned()
+    }
+}
+
+impl CStr {
+    /// Copies the string into a new [`CString`], without aborting on
+    /// allocation failure.
+    ///
+    /// This is the fallible counterpart of `to_owned` and of
+    /// `CString::from(&CStr)`. A `Box<CStr>` can then be obtained with
+    /// [`CString::into_boxed_c_str`], which never allocates.
+    ///
+    /// There is no fallible conversion into `Rc<CStr>` or `Arc<CStr>`, as the
+    /// standard library cannot allocate those without aborting on failure.
+    /// [`ArcCString::try_from_c_str`] can be used instead.
+    ///
+    /// [`CString`]: struct.CString.html
+    /// [`CString::into_boxed_c_str`]: struct.CString.html#method.into_boxed_c_str
+    /// [`ArcCString::try_from_c_str`]: struct.ArcCString.html#method.try_from_c_str
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AllocError`] if memory could not be allocated.
+    ///
+    /// [`AllocError`]: struct.AllocError.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cstr_core::CStr;
+    ///
+    /// let s = CStr::from_bytes_with_nul(b"foo\0").unwrap();
+    /// let boxed = s.try_to_owned().unwrap().into_boxed_c_str();
+    /// assert_eq!(&*boxed, s);
+    /// ```
+    #[inline]
+    pub fn try_to_owned(&self) -> Result<CString, AllocError> {
+        let bytes = self.to_bytes_with_nul();
+        Ok(CString {
+            inner: try_copy(bytes, bytes.len())?.into_boxed_slice(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn try_new() {
+        let s = CString::try_new(b"abc").unwrap();
+        assert_eq!(s.as_bytes_with_nul(), b"abc\0");
+        assert_eq!(
+            CString::try_new(b"a\0c"),
+            Err(TryNewError::Nul(NulError(1, b"a\0c".to_vec())))
+        );
+        assert_eq!(s.try_clone().unwrap(), s);
+        assert_eq!(s.as_c_str().try_to_owned().unwrap(), s);
+    }
+
+    #[test]
+    fn try_from_vec() {
+        let mut v = Vec::with_capacity(16);
+        v.extend_from_slice(b"vec");
+        let s = CString::try_from_vec(v).unwrap();
+        assert_eq!(s.as_bytes_with_nul(), b"vec\0");
+
+        let err = CString::try_from_vec(b"v\0".to_vec()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "nul byte found in provided data at position: 1"
+        );
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn alloc_error() {
+        let mut v = Vec::<u8>::new();
+        let err = TryNewError::from(v.try_reserve_exact(usize::MAX).unwrap_err());
+        assert_eq!(err, TryNewError::Alloc(AllocError::new()));
+        assert_eq!(err.to_string(), "memory allocation failed");
+    }
+}
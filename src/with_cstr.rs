@@ -0,0 +1,204 @@
+use core::error::Error;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::{ptr, slice};
+
+#[cfg(feature = "alloc")]
+use {CString, NulError};
+use {CStr, FromBytesWithNulErrorKind};
+
+/// Size of the stack buffer used by [`with_cstr`] and [`with_cstr_on_stack`],
+/// including the nul terminator.
+///
+/// [`with_cstr`]: fn.with_cstr.html
+/// [`with_cstr_on_stack`]: fn.with_cstr_on_stack.html
+pub const MAX_STACK_ALLOCATION: usize = 384;
+
+/// An error returned from [`with_cstr_on_stack`].
+///
+/// [`with_cstr_on_stack`]: fn.with_cstr_on_stack.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WithCStrError {
+    /// The data contained a nul byte at the given position.
+    InteriorNul(usize),
+    /// The data, with its nul terminator, does not fit in the stack buffer.
+    TooLong,
+}
+
+impl WithCStrError {
+    /// Returns the position of the nul byte in the provided data, if that is
+    /// what caused the conversion to fail.
+    #[inline]
+    pub fn nul_position(&self) -> Option<usize> {
+        match *self {
+            WithCStrError::InteriorNul(pos) => Some(pos),
+            WithCStrError::TooLong => None,
+        }
+    }
+}
+
+impl fmt::Display for WithCStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WithCStrError::InteriorNul(pos) => {
+                write!(f, "nul byte found in provided data at position: {}", pos)
+            }
+            WithCStrError::TooLong => f.write_str("data provided does not fit in the stack buffer"),
+        }
+    }
+}
+
+impl Error for WithCStrError {}
+
+/// Copies `bytes` into a stack buffer, appends a nul terminator and calls `f`
+/// with the result.
+fn on_stack<T, F>(bytes: &[u8], f: F) -> Result<T, WithCStrError>
+where
+    F: FnOnce(&CStr) -> T,
+{
+    if bytes.len() >= MAX_STACK_ALLOCATION {
+        return Err(WithCStrError::TooLong);
+    }
+
+    let mut buf = MaybeUninit::<[u8; MAX_STACK_ALLOCATION]>::uninit();
+    let buf_ptr = buf.as_mut_ptr() as *mut u8;
+    let bytes_with_nul = unsafe {
+        ptr::copy_nonoverlapping(bytes.as_ptr(), buf_ptr, bytes.len());
+        buf_ptr.add(bytes.len()).write(0);
+        slice::from_raw_parts(buf_ptr, bytes.len() + 1)
+    };
+
+    match CStr::from_bytes_with_nul(bytes_with_nul) {
+        Ok(s) => Ok(f(s)),
+        Err(e) => match e.kind() {
+            FromBytesWithNulErrorKind::InteriorNul(pos) => Err(WithCStrError::InteriorNul(pos)),
+            FromBytesWithNulErrorKind::NotNulTerminated => unreachable!(),
+        },
+    }
+}
+
+/// Calls `f` with a temporary C string holding the given data.
+///
+/// This avoids a heap allocation for short strings, which are copied into a
+/// buffer of [`MAX_STACK_ALLOCATION`] bytes on the stack; only longer data is
+/// copied into a [`CString`]. This is meant for the common case of passing a
+/// Rust string to a single FFI call.
+///
+/// [`MAX_STACK_ALLOCATION`]: constant.MAX_STACK_ALLOCATION.html
+/// [`CString`]: struct.CString.html
+///
+/// # Errors
+///
+/// Returns a [`NulError`] if the data contains a nul byte, exactly as
+/// [`CString::new`] would. `f` is not called in that case.
+///
+/// [`NulError`]: struct.NulError.html
+/// [`CString::new`]: struct.CString.html#method.new
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::with_cstr;
+///
+/// let len = with_cstr("hello", |s| s.to_bytes_with_nul().len()).unwrap();
+/// assert_eq!(len, 6);
+///
+/// let err = with_cstr("hel\0lo", |_| ()).unwrap_err();
+/// assert_eq!(err.nul_position(), 3);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn with_cstr<S, T, F>(s: S, f: F) -> Result<T, NulError>
+where
+    S: AsRef<[u8]>,
+    F: FnOnce(&CStr) -> T,
+{
+    let bytes = s.as_ref();
+    if bytes.len() >= MAX_STACK_ALLOCATION {
+        return CString::new(bytes).map(|s| f(&s));
+    }
+    on_stack(bytes, f).map_err(|e| match e {
+        WithCStrError::InteriorNul(pos) => NulError(pos, bytes.to_vec()),
+        WithCStrError::TooLong => unreachable!(),
+    })
+}
+
+/// Calls `f` with a temporary C string holding the given data, without ever
+/// allocating.
+///
+/// This is the same as [`with_cstr`], except that data which does not fit in
+/// the stack buffer results in an error instead of a heap allocation.
+///
+/// [`with_cstr`]: fn.with_cstr.html
+///
+/// # Errors
+///
+/// Returns [`WithCStrError::InteriorNul`] if the data contains a nul byte, and
+/// [`WithCStrError::TooLong`] if it is [`MAX_STACK_ALLOCATION`] bytes or
+/// longer. `f` is not called in either case.
+///
+/// [`WithCStrError::InteriorNul`]: enum.WithCStrError.html#variant.InteriorNul
+/// [`WithCStrError::TooLong`]: enum.WithCStrError.html#variant.TooLong
+/// [`MAX_STACK_ALLOCATION`]: constant.MAX_STACK_ALLOCATION.html
+///
+/// # Examples
+///
+/// ```
+/// use cstr_core::{with_cstr_on_stack, WithCStrError, MAX_STACK_ALLOCATION};
+///
+/// let len = with_cstr_on_stack("hello", |s| s.to_bytes().len());
+/// assert_eq!(len, Ok(5));
+///
+/// let long = [b'a'; MAX_STACK_ALLOCATION];
+/// let err = with_cstr_on_stack(&long[..], |_| ()).unwrap_err();
+/// assert_eq!(err, WithCStrError::TooLong);
+/// ```
+pub fn with_cstr_on_stack<S, T, F>(s: S, f: F) -> Result<T, WithCStrError>
+where
+    S: AsRef<[u8]>,
+    F: FnOnce(&CStr) -> T,
+{
+    on_stack(s.as_ref(), f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn stack() {
+        let r = with_cstr("foo", |s| {
+            assert_eq!(s.to_bytes_with_nul(), b"foo\0");
+            1
+        });
+        assert_eq!(r, Ok(1));
+        assert_eq!(with_cstr("", |s| s.to_bytes().len()), Ok(0));
+
+        let max = [b'a'; MAX_STACK_ALLOCATION - 1];
+        assert_eq!(with_cstr_on_stack(&max[..], |s| s.to_bytes().len()), Ok(max.len()));
+    }
+
+    #[test]
+    fn heap_fallback() {
+        let long: Vec<u8> = (0..1000).map(|i| b'a' + (i % 26) as u8).collect();
+        let r = with_cstr(&long, |s| s.to_bytes() == &long[..]);
+        assert_eq!(r, Ok(true));
+
+        let mut bad = long.clone();
+        bad[500] = 0;
+        assert_eq!(with_cstr(&bad, |_| ()).unwrap_err().nul_position(), 500);
+
+        assert_eq!(with_cstr_on_stack(&long, |_| ()), Err(WithCStrError::TooLong));
+    }
+
+    #[test]
+    fn interior_nul() {
+        let err = with_cstr("a\0b", |_| panic!()).unwrap_err();
+        assert_eq!(err.nul_position(), 1);
+        assert_eq!(err.into_vec(), b"a\0b");
+
+        let err = with_cstr_on_stack("a\0b", |_| panic!()).unwrap_err();
+        assert_eq!(err, WithCStrError::InteriorNul(1));
+        assert_eq!(err.nul_position(), Some(1));
+    }
+}